@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::error;
 use std::fmt;
 use std::str::{self, FromStr};
@@ -175,7 +176,7 @@ pub enum Offset {
 
     /// Offset between local time and UTC
     Custom {
-        /// Hours: -12 to +12
+        /// Hours: -23 to +23
         hours: i8,
 
         /// Minutes: 0 to 59
@@ -183,6 +184,259 @@ pub enum Offset {
     },
 }
 
+#[cfg(feature = "std-time")]
+impl Datetime {
+    /// Returns the current system time as an offset date-time in UTC.
+    ///
+    /// This requires the `std-time` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use toml::value::{Datetime, Offset};
+    ///
+    /// let now = Datetime::now_utc();
+    /// assert!(now.date.is_some() && now.time.is_some());
+    /// assert!(now.offset == Some(Offset::Z));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the system clock is set before the Unix epoch.
+    pub fn now_utc() -> Datetime {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before the Unix epoch");
+        let secs = since_epoch.as_secs();
+        let days = secs / 86_400;
+        let secs_of_day = secs % 86_400;
+
+        Datetime {
+            date: Some(date_from_days(days as i64)),
+            time: Some(Time {
+                hour: (secs_of_day / 3600) as u8,
+                minute: (secs_of_day % 3600 / 60) as u8,
+                second: (secs_of_day % 60) as u8,
+                nanosecond: since_epoch.subsec_nanos(),
+            }),
+            offset: Some(Offset::Z),
+        }
+    }
+}
+
+impl Datetime {
+    /// Returns the RFC 3339 representation of this datetime, as used in TOML
+    /// documents.
+    ///
+    /// Returns `None` if the fields don't form one of the four kinds of TOML
+    /// datetime: there must be a date, a time, or both, and an offset is only
+    /// allowed along with both of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use toml::value::{Datetime, Offset};
+    ///
+    /// let mut dt: Datetime = "1979-05-27T07:32:00".parse().unwrap();
+    /// assert_eq!(dt.to_rfc3339().unwrap(), "1979-05-27T07:32:00");
+    ///
+    /// dt.date = None;
+    /// dt.offset = Some(Offset::Z);
+    /// assert_eq!(dt.to_rfc3339(), None);
+    /// ```
+    pub fn to_rfc3339(&self) -> Option<String> {
+        let valid = match (&self.date, &self.time, &self.offset) {
+            (None, None, _) => false,
+            (Some(_), Some(_), _) | (_, _, None) => true,
+            _ => false,
+        };
+        if valid {
+            Some(self.to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Returns this datetime shifted by the given number of minutes, which may
+    /// be negative.
+    ///
+    /// See `checked_add_seconds` for details.
+    pub fn checked_add_minutes(&self, minutes: i64) -> Option<Datetime> {
+        self.checked_add_seconds(minutes.checked_mul(60)?)
+    }
+
+    /// Returns this datetime shifted by the given number of seconds, which may
+    /// be negative.
+    ///
+    /// The date rolls over into the previous or following days, months and
+    /// years as needed, and the offset, if any, is kept as-is. Returns `None`
+    /// if this is a local date or a local time, which can't be shifted
+    /// unambiguously, or if the result would fall outside of the years 0 to
+    /// 9999.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use toml::value::Datetime;
+    ///
+    /// let dt: Datetime = "2021-12-31T23:30:00Z".parse().unwrap();
+    /// let later = dt.checked_add_minutes(45).unwrap();
+    /// assert_eq!(later.to_string(), "2022-01-01T00:15:00Z");
+    ///
+    /// let time: Datetime = "23:30:00".parse().unwrap();
+    /// assert!(time.checked_add_minutes(45).is_none());
+    /// ```
+    pub fn checked_add_seconds(&self, seconds: i64) -> Option<Datetime> {
+        let (date, time) = match (&self.date, &self.time) {
+            (Some(date), Some(time)) => (date, time),
+            _ => return None,
+        };
+
+        let secs_of_day =
+            i64::from(time.hour) * 3600 + i64::from(time.minute) * 60 + i64::from(time.second);
+        let secs = secs_of_day.checked_add(seconds)?;
+        let days = days_from_date(date).checked_add(secs.div_euclid(86_400))?;
+        let secs_of_day = secs.rem_euclid(86_400);
+
+        // Bounds the year to 0..=9999, the range of four digit years
+        if !(-719_528..=2_932_896).contains(&days) {
+            return None;
+        }
+
+        Some(Datetime {
+            date: Some(date_from_days(days)),
+            time: Some(Time {
+                hour: (secs_of_day / 3600) as u8,
+                minute: (secs_of_day % 3600 / 60) as u8,
+                second: (secs_of_day % 60) as u8,
+                nanosecond: time.nanosecond,
+            }),
+            offset: self.offset.clone(),
+        })
+    }
+
+    /// Returns whether both datetimes are offset date-times denoting the same
+    /// instant, even if they are written with different offsets.
+    ///
+    /// Unlike `==`, which compares the fields as written, this converts both
+    /// datetimes to UTC first. Local date-times, local dates and local times
+    /// don't denote an instant, so this returns `false` if either datetime
+    /// lacks a date, a time or an offset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use toml::value::Datetime;
+    ///
+    /// let a: Datetime = "1979-05-27T00:32:00-07:00".parse().unwrap();
+    /// let b: Datetime = "1979-05-27T07:32:00Z".parse().unwrap();
+    /// assert!(a != b);
+    /// assert!(a.same_instant(&b));
+    /// ```
+    pub fn same_instant(&self, other: &Datetime) -> bool {
+        match (self.utc_instant(), other.utc_instant()) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Compares two datetimes of the same kind.
+    ///
+    /// Local dates, local times and local date-times are ordered by their
+    /// date and then their time, as they would be sorted on a calendar
+    /// without regard to any time zone. Offset date-times are ordered by the
+    /// instant they denote, as with `same_instant`. Returns `None` if the two
+    /// datetimes are of different kinds, such as a local date and a local
+    /// date-time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use toml::value::Datetime;
+    ///
+    /// let a: Datetime = "1979-05-27T07:32:00".parse().unwrap();
+    /// let b: Datetime = "1979-05-27T10:00:00".parse().unwrap();
+    /// let c: Datetime = "1979-05-28".parse().unwrap();
+    /// assert_eq!(a.cmp_local(&b), Some(Ordering::Less));
+    /// assert_eq!(a.cmp_local(&c), None);
+    /// ```
+    pub fn cmp_local(&self, other: &Datetime) -> Option<Ordering> {
+        let date = |dt: &Datetime| dt.date.as_ref().map(|d| (d.year, d.month, d.day));
+        let time = |dt: &Datetime| {
+            dt.time
+                .as_ref()
+                .map(|t| (t.hour, t.minute, t.second, t.nanosecond))
+        };
+
+        let same_kind = self.date.is_some() == other.date.is_some()
+            && self.time.is_some() == other.time.is_some()
+            && self.offset.is_some() == other.offset.is_some();
+        if !same_kind {
+            return None;
+        }
+        match (self.utc_instant(), other.utc_instant()) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            (None, None) if self.offset.is_none() => Some(
+                date(self)
+                    .cmp(&date(other))
+                    .then_with(|| time(self).cmp(&time(other))),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Rewrites an offset of `+00:00` or `-00:00` into `Z`.
+    ///
+    /// Parsing keeps offsets as they were written, so `+00:00` and `Z` are
+    /// different values which only compare equal with `same_instant`. After
+    /// normalization both are written as the shorter `Z` when serialized.
+    /// Other offsets, and datetimes without an offset, are left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use toml::value::Datetime;
+    ///
+    /// let mut dt: Datetime = "1979-05-27T07:32:00+00:00".parse().unwrap();
+    /// dt.normalize_utc_offset();
+    /// assert_eq!(dt.to_string(), "1979-05-27T07:32:00Z");
+    /// ```
+    pub fn normalize_utc_offset(&mut self) {
+        if let Some(Offset::Custom {
+            hours: 0,
+            minutes: 0,
+        }) = self.offset
+        {
+            self.offset = Some(Offset::Z);
+        }
+    }
+
+    /// Returns the number of seconds since the Unix epoch in UTC and the
+    /// nanoseconds of this offset date-time.
+    fn utc_instant(&self) -> Option<(i64, u32)> {
+        let (date, time, offset) = match (&self.date, &self.time, &self.offset) {
+            (Some(date), Some(time), Some(offset)) => (date, time, offset),
+            _ => return None,
+        };
+        let offset_minutes = match *offset {
+            Offset::Z => 0,
+            Offset::Custom { hours, minutes } => {
+                let minutes = i64::from(minutes);
+                i64::from(hours) * 60 + if hours < 0 { -minutes } else { minutes }
+            }
+        };
+        let secs = days_from_date(date) * 86_400
+            + i64::from(time.hour) * 3600
+            + i64::from(time.minute) * 60
+            + i64::from(time.second)
+            - offset_minutes * 60;
+        Some((secs, time.nanosecond))
+    }
+}
+
 impl fmt::Debug for Datetime {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Display::fmt(self, f)
@@ -237,6 +491,34 @@ impl FromStr for Datetime {
     type Err = DatetimeParseError;
 
     fn from_str(date: &str) -> Result<Datetime, DatetimeParseError> {
+        Datetime::parse(date, false)
+    }
+}
+
+impl Datetime {
+    /// Parses a datetime, strictly following the TOML specification for
+    /// fractional seconds.
+    ///
+    /// Unlike the `FromStr` implementation, which accepts any number of
+    /// fractional digits and truncates them to nanoseconds, this requires
+    /// fractional seconds to have at least millisecond precision, and rejects
+    /// more digits than nanosecond precision can represent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use toml::value::Datetime;
+    ///
+    /// assert!(Datetime::from_str_strict("07:32:00").is_ok());
+    /// assert!(Datetime::from_str_strict("07:32:00.999").is_ok());
+    /// assert!(Datetime::from_str_strict("07:32:00.9").is_err());
+    /// assert!(Datetime::from_str_strict("07:32:00.9999999999").is_err());
+    /// ```
+    pub fn from_str_strict(date: &str) -> Result<Datetime, DatetimeParseError> {
+        Datetime::parse(date, true)
+    }
+
+    fn parse(date: &str, strict: bool) -> Result<Datetime, DatetimeParseError> {
         // Accepted formats:
         //
         // 0000-00-00T00:00:00.00Z
@@ -284,7 +566,7 @@ impl FromStr for Datetime {
             if date.month < 1 || date.month > 12 {
                 return Err(DatetimeParseError { _private: () });
             }
-            if date.day < 1 || date.day > 31 {
+            if date.day < 1 || date.day > last_day(date.year, date.month) {
                 return Err(DatetimeParseError { _private: () });
             }
 
@@ -338,7 +620,7 @@ impl FromStr for Datetime {
                         }
                     }
                 }
-                if end == 0 {
+                if end == 0 || (strict && !(3..=9).contains(&end)) {
                     return Err(DatetimeParseError { _private: () });
                 }
                 chars = whole[end..].chars();
@@ -394,9 +676,15 @@ impl FromStr for Datetime {
                 let m1 = digit(&mut chars)?;
                 let m2 = digit(&mut chars)?;
 
+                let hours = h1 * 10 + h2;
+                let minutes = m1 * 10 + m2;
+                if hours > 23 || minutes > 59 {
+                    return Err(DatetimeParseError { _private: () });
+                }
+
                 Some(Offset::Custom {
-                    hours: sign * (h1 * 10 + h2),
-                    minutes: m1 * 10 + m2,
+                    hours: sign * hours,
+                    minutes,
                 })
             }
         } else {
@@ -417,6 +705,58 @@ impl FromStr for Datetime {
     }
 }
 
+/// Returns the number of days between the Unix epoch and `date`, see
+/// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_date(date: &Date) -> i64 {
+    let month = i64::from(date.month);
+    let year = i64::from(date.year) - if month <= 2 { 1 } else { 0 };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + i64::from(date.day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Converts a number of days since the Unix epoch to a date, see
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+///
+/// The result must fall within the years 0 to 9999.
+fn date_from_days(days: i64) -> Date {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    Date {
+        year: year as u16,
+        month: month as u8,
+        day: day as u8,
+    }
+}
+
+/// Returns the number of days in the given month of the given year.
+// `is_multiple_of` is only stable since Rust 1.87, which is newer than the
+// compilers this crate supports.
+#[allow(clippy::manual_is_multiple_of)]
+fn last_day(year: u16, month: u8) -> u8 {
+    match month {
+        2 => {
+            if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) {
+                29
+            } else {
+                28
+            }
+        }
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
 fn digit(chars: &mut str::Chars<'_>) -> Result<u8, DatetimeParseError> {
     match chars.next() {
         Some(c) if '0' <= c && c <= '9' => Ok(c as u8 - b'0'),
@@ -5,12 +5,17 @@
 //! provided at the top of the crate.
 
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::error;
 use std::f64;
 use std::fmt;
+use std::io;
 use std::iter;
 use std::marker::PhantomData;
+use std::num::IntErrorKind;
+use std::ops::Range;
+use std::rc::Rc;
 use std::str;
 use std::vec;
 
@@ -21,28 +26,879 @@ use serde::de::IntoDeserializer;
 use crate::datetime;
 use crate::spanned;
 use crate::tokens::{Error as TokenError, Span, Token, Tokenizer};
+use crate::value::path_segment;
 
 /// Type Alias for a TOML Table pair
 type TablePair<'a> = ((Span, Cow<'a, str>), Value<'a>);
 
+/// The offset of the key which first defined each key path of a table, used
+/// to find duplicate keys without searching the table.
+type KeyIndex<'a> = HashMap<Vec<Cow<'a, str>>, usize>;
+
 /// Deserializes a byte slice into a type.
 ///
 /// This function will attempt to interpret `bytes` as UTF-8 data and then
 /// deserialize `T` from the TOML document provided.
 pub fn from_slice<'de, T>(bytes: &'de [u8]) -> Result<T, Error>
 where
-    T: de::Deserialize<'de>,
+    T: de::Deserialize<'de>,
+{
+    match str::from_utf8(bytes) {
+        Ok(s) => from_str(s),
+        Err(e) => Err(Error::custom(None, e.to_string())),
+    }
+}
+
+/// Deserializes a string into a type.
+///
+/// This function will attempt to interpret `s` as a TOML document and
+/// deserialize `T` from the document.
+///
+/// # Examples
+///
+/// ```
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     title: String,
+///     owner: Owner,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Owner {
+///     name: String,
+/// }
+///
+/// fn main() {
+///     let config: Config = toml::from_str(r#"
+///         title = 'TOML Example'
+///
+///         [owner]
+///         name = 'Lisa'
+///     "#).unwrap();
+///
+///     assert_eq!(config.title, "TOML Example");
+///     assert_eq!(config.owner.name, "Lisa");
+/// }
+/// ```
+pub fn from_str<'de, T>(s: &'de str) -> Result<T, Error>
+where
+    T: de::Deserialize<'de>,
+{
+    let mut d = Deserializer::new(s);
+    let ret = T::deserialize(&mut d)?;
+    d.end()?;
+    Ok(ret)
+}
+
+/// Deserializes a string into a type like `from_str`, also returning the
+/// keys of the document which the type didn't use.
+///
+/// Keys are unused when the type ignores them, as derived implementations do
+/// for fields they don't know about unless `#[serde(deny_unknown_fields)]` is
+/// given. Reporting them instead of failing is useful to warn about typos in
+/// configuration files. Each key is given as a dotted path in the same format
+/// as `Value::diff`, with array elements named by their index. Only the
+/// outermost unused key is reported for an unused table.
+///
+/// # Examples
+///
+/// ```
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     name: String,
+/// }
+///
+/// let (config, unused) = toml::from_str_reporting_unused::<Config>(
+///     "name = 'app'\nnmae = 'typo'\n[extra]\na = 1",
+/// )
+/// .unwrap();
+/// assert_eq!(config.name, "app");
+/// assert_eq!(unused, ["nmae", "extra"]);
+/// ```
+pub fn from_str_reporting_unused<'de, T>(s: &'de str) -> Result<(T, Vec<String>), Error>
+where
+    T: de::Deserialize<'de>,
+{
+    let unused = Rc::new(RefCell::new(Vec::new()));
+    let mut d = Deserializer::new(s);
+    d.tracker = Some(Tracker {
+        unused: Some(unused.clone()),
+        ..Tracker::default()
+    });
+    let ret = T::deserialize(&mut d)?;
+    d.end()?;
+    let unused = std::mem::take(&mut *unused.borrow_mut());
+    Ok((ret, unused))
+}
+
+/// Maps the dotted key paths of values to their spans in the source, as
+/// returned by `from_str_with_spans`.
+pub type SpanMap = HashMap<String, Range<usize>>;
+
+/// Deserializes a string into a type like `from_str`, also returning the
+/// spans of the values it was deserialized from.
+///
+/// This makes it possible to point at the source of a value which is only
+/// found to be invalid after deserialization, without having to wrap every
+/// field in `Spanned`. Keys are given as dotted paths in the same format as
+/// `Value::diff`, with array elements named by their index, and spans are
+/// byte offsets into `s`. Every value assigned with `key = value` has a
+/// span, including the entries of inline tables and the elements of arrays,
+/// but tables defined by a `[table]` header don't. Keys which the type
+/// ignores have a span too, but the values nested inside of them don't.
+///
+/// # Examples
+///
+/// ```
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     server: Server,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     port: u32,
+/// }
+///
+/// let doc = "[server]\nport = 70000\n";
+/// let (config, spans) = toml::from_str_with_spans::<Config>(doc).unwrap();
+/// assert!(config.server.port > 65535);
+/// assert_eq!(&doc[spans["server.port"].clone()], "70000");
+/// ```
+pub fn from_str_with_spans<'de, T>(s: &'de str) -> Result<(T, SpanMap), Error>
+where
+    T: de::Deserialize<'de>,
+{
+    let spans = Rc::new(RefCell::new(HashMap::new()));
+    let mut d = Deserializer::new(s);
+    d.tracker = Some(Tracker {
+        spans: Some(spans.clone()),
+        ..Tracker::default()
+    });
+    let ret = T::deserialize(&mut d)?;
+    d.end()?;
+    let spans = std::mem::take(&mut *spans.borrow_mut());
+    Ok((ret, spans))
+}
+
+/// Lazily deserializes the elements of a top-level array of tables, such as
+/// the `[[records]]` of a log-like document, into a type.
+///
+/// Each element is parsed and deserialized only when the iterator reaches
+/// it, so the records never have to be held in memory at once. An element
+/// is made of the `[[key]]` header with its key/value pairs and any
+/// sub-tables following it, such as `[key.sub]`. Everything else in the
+/// document is parsed, to find the element headers and report syntax
+/// errors, but otherwise skipped; this includes a `key` defined by other
+/// means than `[[key]]` headers, such as an inline array.
+///
+/// Errors are yielded in place of the element they occur in. The iterator
+/// ends after a syntax error, but continues with the next element after an
+/// error from deserializing one into `T`. Unlike with `from_str`, duplicate
+/// keys across different elements aren't detected.
+///
+/// # Examples
+///
+/// ```
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Record {
+///     level: String,
+/// }
+///
+/// let doc = "[[records]]\nlevel = 'info'\n[[records]]\nlevel = 'warn'\n";
+/// let levels = toml::de::stream_array_of_tables::<Record>(doc, "records")
+///     .map(|record| record.map(|r| r.level))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(levels, ["info", "warn"]);
+/// ```
+pub fn stream_array_of_tables<'a, T>(
+    s: &'a str,
+    key: &'a str,
+) -> impl Iterator<Item = Result<T, Error>> + 'a
+where
+    T: de::DeserializeOwned + 'a,
+{
+    ArrayOfTables {
+        de: Deserializer::new(s),
+        key,
+        next: Some(Table {
+            at: 0,
+            header: Vec::new(),
+            values: Some(Vec::new()),
+            array: false,
+        }),
+        index: KeyIndex::new(),
+        done: false,
+        _marker: PhantomData,
+    }
+}
+
+struct ArrayOfTables<'a, T> {
+    de: Deserializer<'a>,
+    key: &'a str,
+    /// The table whose header was read last, with the key/value pairs read
+    /// after it so far. `None` once the end of the input is reached.
+    next: Option<Table<'a>>,
+    /// The key paths defined so far in `next`.
+    index: KeyIndex<'a>,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T> ArrayOfTables<'a, T>
+where
+    T: de::DeserializeOwned,
+{
+    /// Reads the next complete table of the document. Key/value pairs before
+    /// the first header are returned as a table with an empty header.
+    fn read_table(&mut self) -> Result<Option<Table<'a>>, Error> {
+        while let Some(line) = self.de.line()? {
+            match line {
+                Line::Table {
+                    at,
+                    mut header,
+                    array,
+                } => {
+                    let mut table = Table {
+                        at,
+                        header: Vec::new(),
+                        values: Some(Vec::new()),
+                        array,
+                    };
+                    while let Some(part) = header.next().map_err(|e| self.de.token_error(e))? {
+                        table.header.push(part);
+                    }
+                    self.index.clear();
+                    if let Some(prev) = self.next.replace(table) {
+                        return Ok(Some(prev));
+                    }
+                }
+                Line::KeyValue(key, value) => {
+                    let table = self.next.as_mut().expect("read past the end of the input");
+                    self.de
+                        .add_dotted_key(key, value, table.values.as_mut().unwrap(), &mut self.index)
+                        .map_err(|e| e.duplicate_key_context(&table.header))?;
+                }
+            }
+        }
+        Ok(self.next.take())
+    }
+
+    /// Reads the tables making up the next element of the array.
+    fn read_element(&mut self) -> Result<Option<Vec<Table<'a>>>, Error> {
+        let key = self.key;
+        let first = loop {
+            match self.read_table()? {
+                Some(table)
+                    if table.array && table.header.len() == 1 && table.header[0].1 == key =>
+                {
+                    break table
+                }
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        };
+        let mut tables = vec![first];
+        while let Some(table) = &self.next {
+            if table.header.len() < 2 || table.header[0].1 != key {
+                break;
+            }
+            tables.extend(self.read_table()?);
+        }
+        Ok(Some(tables))
+    }
+
+    /// Deserializes an element as if its tables made up a whole document.
+    fn deserialize_element(&mut self, mut tables: Vec<Table<'a>>) -> Result<T, Error> {
+        for table in &mut tables {
+            table.header.remove(0);
+        }
+        tables[0].array = false;
+        let table_indices = build_table_indices(&tables);
+        let table_pindices = build_table_pindices(&tables);
+        self.de.check_arrays_of_tables(&tables, &table_indices)?;
+
+        let res = T::deserialize(MapVisitor {
+            values: Vec::new().into_iter().peekable(),
+            next_value: None,
+            depth: 0,
+            cur: 0,
+            cur_parent: 0,
+            max: tables.len(),
+            table_indices: &table_indices,
+            table_pindices: &table_pindices,
+            tables: &mut tables,
+            array: false,
+            tracker: None,
+            de: &mut self.de,
+        });
+        res.map_err(|mut err| {
+            err.add_key_context(self.key);
+            err.fix_offset(|| tables.last().map(|table| table.at));
+            err
+        })
+    }
+}
+
+impl<'a, T> Iterator for ArrayOfTables<'a, T>
+where
+    T: de::DeserializeOwned,
+{
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Result<T, Error>> {
+        if self.done {
+            return None;
+        }
+        let res = match self.read_element() {
+            Ok(Some(tables)) => self.deserialize_element(tables),
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Err(err) => {
+                self.done = true;
+                Err(err)
+            }
+        };
+        let de = &self.de;
+        Some(res.map_err(|mut err| {
+            err.fix_linecol(de.base, |at| de.to_linecol(at));
+            err
+        }))
+    }
+}
+
+/// Deserializes a TOML document read from `reader` into a type.
+///
+/// This saves reading the document into a `String` first. The whole input is
+/// currently read before parsing starts, and input which isn't valid UTF-8 is
+/// rejected with an error.
+///
+/// # Examples
+///
+/// ```
+/// let input = &b"name = 'toml'"[..];
+/// let value: toml::Value = toml::from_reader(input).unwrap();
+/// assert_eq!(value["name"].as_str(), Some("toml"));
+/// ```
+pub fn from_reader<R, T>(mut reader: R) -> Result<T, Error>
+where
+    R: io::Read,
+    T: de::DeserializeOwned,
+{
+    let mut bytes = Vec::new();
+    if let Err(e) = reader.read_to_end(&mut bytes) {
+        return Err(Error::custom(None, format!("failed to read input: {}", e)));
+    }
+    match String::from_utf8(bytes) {
+        Ok(s) => from_str(&s),
+        Err(e) => Err(Error::custom(
+            None,
+            format!("input is not valid UTF-8: {}", e.utf8_error()),
+        )),
+    }
+}
+
+/// Parses a string into a tree of values annotated with their source spans.
+///
+/// The spans are byte offsets into `s`, which allows reporting precise
+/// locations for problems that are only discovered after parsing.
+///
+/// # Examples
+///
+/// ```
+/// use toml::spanned_value::ValueKind;
+///
+/// let doc = toml::from_str_spanned("port = 8080\n").unwrap();
+/// if let ValueKind::Table(table) = doc.get_ref() {
+///     assert_eq!(table["port"].span(), (7, 11));
+/// }
+/// ```
+pub fn from_str_spanned(s: &str) -> Result<crate::spanned_value::SpannedValue, Error> {
+    from_str(s)
+}
+
+/// Returns the span of the value which starts at byte `offset` of `input`.
+///
+/// This is intended for editing tools: given the offset just after the `=`
+/// of a key/value pair, it finds the complete extent of the value, including
+/// all of an array or inline table, so that it can be highlighted or
+/// replaced. Whitespace before the value is skipped.
+///
+/// # Examples
+///
+/// ```
+/// let doc = "a = [1, { b = 2 }] # comment\n";
+/// let (start, end) = toml::de::value_span(doc, 3).unwrap();
+/// assert_eq!(&doc[start..end], "[1, { b = 2 }]");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `offset` is out of bounds or not on a `char` boundary.
+pub fn value_span(input: &str, offset: usize) -> Result<(usize, usize), Error> {
+    let mut d = Deserializer::new(input);
+    d.tokens.seek(offset);
+    d.eat_whitespace()?;
+    let value = d.value()?;
+    Ok((value.start, value.end))
+}
+
+/// Returns the source text of the value which starts at byte `offset` of
+/// `input`, as found by `value_span`.
+///
+/// The text is returned exactly as written, so strings keep their original
+/// quotes and escapes and numbers their original formatting. This is useful
+/// for format-preserving editors, which must write unchanged values back as
+/// they were.
+///
+/// # Examples
+///
+/// ```
+/// let doc = "a = 'C:\\raw' # comment\nb = 0xdead_beef\n";
+/// assert_eq!(toml::de::raw_value(doc, 3).unwrap(), "'C:\\raw'");
+/// assert_eq!(toml::de::raw_value(doc, 26).unwrap(), "0xdead_beef");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `offset` is out of bounds or not on a `char` boundary.
+pub fn raw_value(input: &str, offset: usize) -> Result<&str, Error> {
+    let (start, end) = value_span(input, offset)?;
+    Ok(&input[start..end])
+}
+
+/// Returns the key and span of every table header in `input`, in document
+/// order.
+///
+/// This is intended for editing tools which want to navigate between tables,
+/// e.g. to jump to `[a.b]`. Each span starts at the opening `[` of the header
+/// and ends at the end of its line, including any trailing comment but not
+/// the newline. Headers of arrays of tables are reported once for each
+/// element.
+///
+/// # Examples
+///
+/// ```
+/// let doc = "a = 1\n[b]\nc = 2\n[[d.e]] # first\n";
+/// let spans = toml::de::table_spans(doc).unwrap();
+/// assert_eq!(spans[0].0, ["b"]);
+/// assert_eq!(spans[1].0, ["d", "e"]);
+/// let (start, end) = spans[1].1;
+/// assert_eq!(&doc[start..end], "[[d.e]] # first");
+/// ```
+pub fn table_spans(input: &str) -> Result<Vec<TableSpan>, Error> {
+    let mut d = Deserializer::new(input);
+    let tables = d.tables()?;
+    Ok(tables
+        .into_iter()
+        .filter(|table| !table.header.is_empty())
+        .map(|table| {
+            let line = &input[table.at..];
+            let line = &line[..line.find('\n').unwrap_or(line.len())];
+            let end = table.at + line.trim_end_matches('\r').len();
+            let key = table.header.into_iter().map(|(_, k)| k.into_owned());
+            (key.collect(), (table.at, end))
+        })
+        .collect())
+}
+
+/// Returns every table header and key of `input` with the comment lines
+/// directly above it, in document order.
+///
+/// This is intended for tools such as documentation generators. Keys are
+/// given as dotted paths in the same format as `Value::diff`, with headers of
+/// arrays of tables reported once for each element but without an index.
+/// Only the block of comment lines immediately before a header or key is
+/// attached to it, so a blank line or another key in between detaches them.
+/// The `#` and a single space after it are removed from each line. Keys
+/// without a comment are reported with an empty list.
+///
+/// # Examples
+///
+/// ```
+/// let doc = "# The name\n# of the app\nname = 'x'\n\n# Ignored\n\n[server]\nport = 80\n";
+/// let comments = toml::de::key_comments(doc).unwrap();
+/// assert_eq!(comments[0], ("name".to_string(), vec!["The name".to_string(), "of the app".to_string()]));
+/// assert_eq!(comments[1], ("server".to_string(), vec![]));
+/// assert_eq!(comments[2], ("server.port".to_string(), vec![]));
+/// ```
+pub fn key_comments(input: &str) -> Result<Vec<(String, Vec<String>)>, Error> {
+    let mut d = Deserializer::new(input);
+    let mut ret = Vec::new();
+    let mut header = Vec::new();
+    let mut prev = 0;
+    loop {
+        d.eat_blank_lines()?;
+        let start = d.tokens.current();
+        let key = match d.line()? {
+            Some(Line::Table {
+                header: mut parts, ..
+            }) => {
+                header.clear();
+                while let Some((_, part)) = parts.next().map_err(|e| d.token_error(e))? {
+                    header.push(path_segment(&part));
+                }
+                header.join(".")
+            }
+            Some(Line::KeyValue(key, _)) => {
+                let key = key.iter().map(|(_, part)| path_segment(part));
+                header
+                    .iter()
+                    .cloned()
+                    .chain(key)
+                    .collect::<Vec<_>>()
+                    .join(".")
+            }
+            None => break,
+        };
+
+        // The last line is the indentation of the key itself.
+        let mut lines = input[prev..start].split('\n').rev().skip(1);
+        let mut comments = Vec::new();
+        while let Some(comment) = lines
+            .next()
+            .map(|line| line.trim_start_matches('\u{feff}').trim())
+            .and_then(|line| line.strip_prefix('#'))
+        {
+            comments.push(comment.strip_prefix(' ').unwrap_or(comment).to_string());
+        }
+        comments.reverse();
+        ret.push((key, comments));
+        prev = d.tokens.current();
+    }
+    Ok(ret)
+}
+
+/// A table header key and its span, as returned by `table_spans`.
+type TableSpan = (Vec<String>, (usize, usize));
+
+/// Returns the comment on the first line of `input`, if the document starts
+/// with one.
+///
+/// Some tools give the first line of a configuration file a special meaning,
+/// such as a `#!` interpreter line or a `# toml-version: 1.0` directive. This
+/// returns that comment, including its leading `#`, so it can be inspected
+/// without having to recognize comments by hand. A UTF-8 byte order mark is
+/// skipped, but anything else before the `#`, including whitespace, means
+/// there is no leading directive. All comments are otherwise ignored when
+/// deserializing.
+///
+/// # Examples
+///
+/// ```
+/// let doc = "#!/usr/bin/env tool\n# a comment\nname = 'x'\n";
+/// assert_eq!(toml::de::leading_directive(doc), Some("#!/usr/bin/env tool"));
+/// assert_eq!(toml::de::leading_directive("name = 'x' # a comment"), None);
+/// ```
+pub fn leading_directive(input: &str) -> Option<&str> {
+    match Tokenizer::new(input).next() {
+        Ok(Some((_, Token::Comment(comment)))) => Some(comment),
+        _ => None,
+    }
+}
+
+/// Splits a dotted key path such as `a.b.c` into its segments.
+///
+/// Segments follow the TOML syntax for keys: they are either bare keys or
+/// quoted strings, so `table."name.other".value` has the three segments
+/// `table`, `name.other` and `value`. Whitespace around the dots is ignored.
+///
+/// Returns `None` if `path` isn't a valid dotted key, including when it is
+/// empty.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(
+///     toml::parse_path("table.\"name.other\".value"),
+///     Some(vec!["table".to_string(), "name.other".to_string(), "value".to_string()])
+/// );
+/// assert_eq!(toml::parse_path("a..b"), None);
+/// ```
+pub fn parse_path(path: &str) -> Option<Vec<String>> {
+    let mut tokens = Tokenizer::new(path);
+    let mut segments = Vec::new();
+    loop {
+        tokens.eat_whitespace().ok()?;
+        match tokens.next().ok()? {
+            Some((_, Token::Keylike(key))) => segments.push(key.to_string()),
+            Some((
+                _,
+                Token::String {
+                    val,
+                    multiline: false,
+                    ..
+                },
+            )) => segments.push(val.into_owned()),
+            _ => return None,
+        }
+        tokens.eat_whitespace().ok()?;
+        match tokens.next().ok()? {
+            Some((_, Token::Period)) => {}
+            None => return Some(segments),
+            _ => return None,
+        }
+    }
+}
+
+/// Decodes a single TOML string literal, such as `"a\tb"` or `'C:\dir'`.
+///
+/// `literal` must consist of exactly one basic, literal or multi-line string,
+/// including its quotes and without surrounding whitespace. Escape sequences
+/// are decoded as when deserializing a document. The result borrows from
+/// `literal` when no decoding was needed, so callers can tell whether the
+/// string had to be copied.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// assert_eq!(toml::unescape(r#""a\tb""#).unwrap(), "a\tb");
+/// assert!(matches!(toml::unescape("'C:\\dir'"), Ok(Cow::Borrowed("C:\\dir"))));
+/// assert!(toml::unescape("\"a\" b").is_err());
+/// ```
+pub fn unescape(literal: &str) -> Result<Cow<'_, str>, Error> {
+    let mut d = Deserializer::new(literal);
+    let val = match d.next()? {
+        Some((_, Token::String { val, .. })) => val,
+        Some((span, token)) => {
+            return Err(d.error(
+                span.start,
+                ErrorKind::Wanted {
+                    expected: "a string",
+                    found: token.describe(),
+                },
+            ))
+        }
+        None => {
+            return Err(d.error(
+                0,
+                ErrorKind::Wanted {
+                    expected: "a string",
+                    found: "eof",
+                },
+            ))
+        }
+    };
+    match d.next()? {
+        Some((span, token)) => Err(d.error(
+            span.start,
+            ErrorKind::Wanted {
+                expected: "nothing after the string",
+                found: token.describe(),
+            },
+        )),
+        None => Ok(val),
+    }
+}
+
+/// Deserializes an `i64` from either a TOML integer or a TOML string
+/// containing a base-10 integer.
+///
+/// This is intended to be used with `#[serde(deserialize_with = "...")]` for
+/// documents which store numbers as quoted strings.
+///
+/// # Examples
+///
+/// ```
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     #[serde(deserialize_with = "toml::de::integer_from_str_or_int")]
+///     port: i64,
+/// }
+///
+/// let config: Config = toml::from_str("port = \"8080\"").unwrap();
+/// assert_eq!(config.port, 8080);
+/// ```
+pub fn integer_from_str_or_int<'de, D>(deserializer: D) -> Result<i64, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct IntegerVisitor;
+
+    impl<'de> de::Visitor<'de> for IntegerVisitor {
+        type Value = i64;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("an integer or a string containing an integer")
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<i64, E>
+        where
+            E: de::Error,
+        {
+            Ok(v)
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<i64, E>
+        where
+            E: de::Error,
+        {
+            if v <= i64::MAX as u64 {
+                Ok(v as i64)
+            } else {
+                Err(de::Error::invalid_value(de::Unexpected::Unsigned(v), &self))
+            }
+        }
+
+        fn visit_str<E>(self, s: &str) -> Result<i64, E>
+        where
+            E: de::Error,
+        {
+            s.parse()
+                .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(s), &self))
+        }
+    }
+
+    deserializer.deserialize_any(IntegerVisitor)
+}
+
+/// Deserializes an `f64` from either a TOML float or a TOML string containing
+/// a float.
+///
+/// This is the floating point counterpart of [`integer_from_str_or_int`].
+pub fn float_from_str_or_float<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct FloatVisitor;
+
+    impl<'de> de::Visitor<'de> for FloatVisitor {
+        type Value = f64;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a float or a string containing a float")
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<f64, E>
+        where
+            E: de::Error,
+        {
+            Ok(v)
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<f64, E>
+        where
+            E: de::Error,
+        {
+            Ok(v as f64)
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<f64, E>
+        where
+            E: de::Error,
+        {
+            Ok(v as f64)
+        }
+
+        fn visit_str<E>(self, s: &str) -> Result<f64, E>
+        where
+            E: de::Error,
+        {
+            s.parse()
+                .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(s), &self))
+        }
+    }
+
+    deserializer.deserialize_any(FloatVisitor)
+}
+
+/// Deserializes a `char` from a TOML string which must contain exactly one
+/// character.
+///
+/// This is intended to be used with `#[serde(deserialize_with = "...")]` and
+/// gives an error for strings such as `"ab"` instead of accepting them.
+pub fn char_from_str<'de, D>(deserializer: D) -> Result<char, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct CharVisitor;
+
+    impl<'de> de::Visitor<'de> for CharVisitor {
+        type Value = char;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a string containing a single character")
+        }
+
+        fn visit_str<E>(self, s: &str) -> Result<char, E>
+        where
+            E: de::Error,
+        {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(de::Error::invalid_value(de::Unexpected::Str(s), &self)),
+            }
+        }
+    }
+
+    deserializer.deserialize_str(CharVisitor)
+}
+
+/// Deserializes a `bool` from either a TOML boolean or a TOML string equal to
+/// `"true"` or `"false"`, ignoring ASCII case.
+///
+/// This is intended to be used with `#[serde(deserialize_with = "...")]`, like
+/// [`integer_from_str_or_int`]. Any other string is an error.
+pub fn bool_from_str_or_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: de::Deserializer<'de>,
 {
-    match str::from_utf8(bytes) {
-        Ok(s) => from_str(s),
-        Err(e) => Err(Error::custom(None, e.to_string())),
+    struct BoolVisitor;
+
+    impl<'de> de::Visitor<'de> for BoolVisitor {
+        type Value = bool;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a boolean or a string containing `true` or `false`")
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<bool, E>
+        where
+            E: de::Error,
+        {
+            Ok(v)
+        }
+
+        fn visit_str<E>(self, s: &str) -> Result<bool, E>
+        where
+            E: de::Error,
+        {
+            if s.eq_ignore_ascii_case("true") {
+                Ok(true)
+            } else if s.eq_ignore_ascii_case("false") {
+                Ok(false)
+            } else {
+                Err(de::Error::invalid_value(de::Unexpected::Str(s), &self))
+            }
+        }
     }
+
+    deserializer.deserialize_any(BoolVisitor)
 }
 
-/// Deserializes a string into a type.
+/// Deserializes a table into a list of its key/value pairs, in the order in
+/// which the keys appear in the document.
 ///
-/// This function will attempt to interpret `s` as a TOML document and
-/// deserialize `T` from the document.
+/// This is intended to be used with `#[serde(deserialize_with = "...")]` for
+/// tables whose key order is meaningful, such as a list of named sections.
+/// Unlike deserializing into a map, the order is kept even without the
+/// `preserve_order` feature.
 ///
 /// # Examples
 ///
@@ -51,35 +907,44 @@ where
 ///
 /// #[derive(Deserialize)]
 /// struct Config {
-///     title: String,
-///     owner: Owner,
-/// }
-///
-/// #[derive(Deserialize)]
-/// struct Owner {
-///     name: String,
+///     #[serde(deserialize_with = "toml::de::ordered_entries")]
+///     steps: Vec<(String, u32)>,
 /// }
 ///
-/// fn main() {
-///     let config: Config = toml::from_str(r#"
-///         title = 'TOML Example'
-///
-///         [owner]
-///         name = 'Lisa'
-///     "#).unwrap();
-///
-///     assert_eq!(config.title, "TOML Example");
-///     assert_eq!(config.owner.name, "Lisa");
-/// }
+/// let config: Config = toml::from_str("[steps]\nfetch = 1\nbuild = 2").unwrap();
+/// assert_eq!(config.steps[0], ("fetch".to_string(), 1));
+/// assert_eq!(config.steps[1], ("build".to_string(), 2));
 /// ```
-pub fn from_str<'de, T>(s: &'de str) -> Result<T, Error>
+pub fn ordered_entries<'de, D, V>(deserializer: D) -> Result<Vec<(String, V)>, D::Error>
 where
-    T: de::Deserialize<'de>,
+    D: de::Deserializer<'de>,
+    V: de::Deserialize<'de>,
 {
-    let mut d = Deserializer::new(s);
-    let ret = T::deserialize(&mut d)?;
-    d.end()?;
-    Ok(ret)
+    struct EntriesVisitor<V>(PhantomData<V>);
+
+    impl<'de, V> de::Visitor<'de> for EntriesVisitor<V>
+    where
+        V: de::Deserialize<'de>,
+    {
+        type Value = Vec<(String, V)>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a table")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: de::MapAccess<'de>,
+        {
+            let mut entries = Vec::new();
+            while let Some(entry) = map.next_entry()? {
+                entries.push(entry);
+            }
+            Ok(entries)
+        }
+    }
+
+    deserializer.deserialize_map(EntriesVisitor(PhantomData))
 }
 
 /// Errors that can occur when deserializing a type.
@@ -94,13 +959,16 @@ struct ErrorInner {
     line: Option<usize>,
     col: usize,
     at: Option<usize>,
+    base: usize,
     message: String,
     key: Vec<String>,
 }
 
-/// Errors that can occur when deserializing a type.
+/// The kind of an error which occurred when deserializing a type, see
+/// `Error::kind`.
 #[derive(Debug, PartialEq, Eq, Clone)]
-enum ErrorKind {
+#[non_exhaustive]
+pub enum ErrorKind {
     /// EOF was reached when looking for a value
     UnexpectedEof,
 
@@ -135,6 +1003,9 @@ enum ErrorKind {
     /// A number failed to parse
     NumberInvalid,
 
+    /// An integer was well-formed but doesn't fit in an `i64`
+    IntegerOverflow,
+
     /// A date or datetime was invalid
     DateInvalid,
 
@@ -152,6 +1023,9 @@ enum ErrorKind {
     /// A previously defined table was redefined as an array.
     RedefineAsArray,
 
+    /// A key holding an array of values was also used as an array of tables.
+    ValueArrayAsArrayOfTables(String),
+
     /// An empty table key was found.
     EmptyTableKey,
 
@@ -181,6 +1055,17 @@ enum ErrorKind {
     /// Dotted key attempted to extend something that is not a table.
     DottedKeyInvalidType,
 
+    /// Non-whitespace content was found after the end of the document.
+    UnexpectedContent,
+
+    /// A key was defined more than once in the same table.
+    DuplicateKey {
+        /// The key that was redefined.
+        key: String,
+        /// Line (0-based) on which the key was first defined.
+        first_line: usize,
+    },
+
     /// An unexpected key was encountered.
     ///
     /// Used when deserializing a struct with a limited set of fields.
@@ -193,17 +1078,18 @@ enum ErrorKind {
 
     /// Unquoted string was found when quoted one was expected
     UnquotedString,
-
-    #[doc(hidden)]
-    __Nonexhaustive,
 }
 
 /// Deserialization implementation for TOML.
 pub struct Deserializer<'a> {
     require_newline_after_table: bool,
     allow_duplciate_after_longer_table: bool,
+    strict_duplicates: bool,
+    integer_to_float_fallback: bool,
+    base: usize,
     input: &'a str,
     tokens: Tokenizer<'a>,
+    tracker: Option<Tracker>,
 }
 
 impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
@@ -216,6 +1102,7 @@ impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
         let mut tables = self.tables()?;
         let table_indices = build_table_indices(&tables);
         let table_pindices = build_table_pindices(&tables);
+        self.check_arrays_of_tables(&tables, &table_indices)?;
 
         let res = visitor.visit_map(MapVisitor {
             values: Vec::new().into_iter().peekable(),
@@ -228,6 +1115,7 @@ impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
             table_pindices: &table_pindices,
             tables: &mut tables,
             array: false,
+            tracker: self.tracker.clone(),
             de: self,
         });
         res.map_err(|mut err| {
@@ -238,7 +1126,7 @@ impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
             // location, by attributing them to the "current table" (the last
             // item in `tables`).
             err.fix_offset(|| tables.last().map(|table| table.at));
-            err.fix_linecol(|at| self.to_linecol(at));
+            err.fix_linecol(self.base, |at| self.to_linecol(at));
             err
         })
     }
@@ -273,6 +1161,7 @@ impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
                     visitor.visit_enum(InlineTableDeserializer {
                         values: values.into_iter(),
                         next_value: None,
+                        tracker: None,
                     })
                 }
             }
@@ -394,9 +1283,52 @@ struct MapVisitor<'de, 'b> {
     table_pindices: &'b HashMap<Vec<Cow<'de, str>>, Vec<usize>>,
     tables: &'b mut [Table<'de>],
     array: bool,
+    tracker: Option<Tracker>,
     de: &'b mut Deserializer<'de>,
 }
 
+/// Keeps track of the path of the value being deserialized, to record the
+/// keys which a type ignores (see `from_str_reporting_unused`) or the spans
+/// of values (see `from_str_with_spans`).
+#[derive(Clone, Default)]
+struct Tracker {
+    unused: Option<Rc<RefCell<Vec<String>>>>,
+    spans: Option<Rc<RefCell<SpanMap>>>,
+    path: Vec<String>,
+    next_index: usize,
+}
+
+impl Tracker {
+    fn child(&self, key: &str) -> Tracker {
+        let mut path = self.path.clone();
+        path.push(path_segment(key));
+        Tracker {
+            unused: self.unused.clone(),
+            spans: self.spans.clone(),
+            path,
+            next_index: 0,
+        }
+    }
+
+    fn element(&mut self) -> Tracker {
+        let index = self.next_index;
+        self.next_index += 1;
+        self.child(&index.to_string())
+    }
+
+    fn report(&self) {
+        if let Some(ref unused) = self.unused {
+            unused.borrow_mut().push(self.path.join("."));
+        }
+    }
+
+    fn record_span(&self, start: usize, end: usize) {
+        if let Some(ref spans) = self.spans {
+            spans.borrow_mut().insert(self.path.join("."), start..end);
+        }
+    }
+}
+
 impl<'de, 'b> de::MapAccess<'de> for MapVisitor<'de, 'b> {
     type Error = Error;
 
@@ -507,7 +1439,8 @@ impl<'de, 'b> de::MapAccess<'de> for MapVisitor<'de, 'b> {
         V: de::DeserializeSeed<'de>,
     {
         if let Some((k, v)) = self.next_value.take() {
-            match seed.deserialize(ValueDeserializer::new(v)) {
+            let tracker = self.tracker.as_ref().map(|u| u.child(&k.1));
+            match seed.deserialize(ValueDeserializer::new(v).with_tracker(tracker)) {
                 Ok(v) => return Ok(v),
                 Err(mut e) => {
                     e.add_key_context(&k.1);
@@ -518,6 +1451,10 @@ impl<'de, 'b> de::MapAccess<'de> for MapVisitor<'de, 'b> {
 
         let array =
             self.tables[self.cur].array && self.depth == self.tables[self.cur].header.len() - 1;
+        let tracker = self
+            .tracker
+            .as_ref()
+            .map(|u| u.child(&self.tables[self.cur].header[self.depth].1));
         self.cur += 1;
         let res = seed.deserialize(MapVisitor {
             values: Vec::new().into_iter().peekable(),
@@ -530,6 +1467,7 @@ impl<'de, 'b> de::MapAccess<'de> for MapVisitor<'de, 'b> {
             table_indices: &*self.table_indices,
             table_pindices: &*self.table_pindices,
             tables: &mut *self.tables,
+            tracker,
             de: &mut *self.de,
         });
         res.map_err(|mut e| {
@@ -592,6 +1530,7 @@ impl<'de, 'b> de::SeqAccess<'de> for MapVisitor<'de, 'b> {
             table_indices: &*self.table_indices,
             table_pindices: &*self.table_pindices,
             tables: &mut self.tables,
+            tracker: self.tracker.as_mut().map(Tracker::element),
             de: &mut self.de,
         })?;
         self.cur_parent = next;
@@ -697,13 +1636,59 @@ impl<'de, 'b> de::Deserializer<'de> for MapVisitor<'de, 'b> {
         })
     }
 
+    fn deserialize_ignored_any<V>(mut self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        // The tables still have to be visited so later keys are found in the
+        // right place, but nothing inside them is reported separately.
+        if let Some(tracker) = self.tracker.take() {
+            tracker.report();
+        }
+        self.deserialize_any(visitor)
+    }
+
+    // Unit structs are serialized as empty tables.
+    fn deserialize_unit_struct<V>(
+        mut self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.array {
+            return self.deserialize_any(visitor);
+        }
+        match de::MapAccess::next_key::<de::IgnoredAny>(&mut self)? {
+            None => visitor.visit_unit(),
+            Some(_) => Err(de::Error::invalid_type(de::Unexpected::Map, &visitor)),
+        }
+    }
+
     serde::forward_to_deserialize_any! {
         bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string seq
         bytes byte_buf map unit identifier
-        ignored_any unit_struct tuple_struct tuple
+        tuple_struct tuple
     }
 }
 
+macro_rules! deserialize_int_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Error>
+            where
+                V: de::Visitor<'de>,
+            {
+                match self.key.parse() {
+                    Ok(n) => visitor.$visit(n),
+                    Err(_) => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
 struct StrDeserializer<'a> {
     span: Option<Span>,
     key: Cow<'a, str>,
@@ -764,8 +1749,21 @@ impl<'de> de::Deserializer<'de> for StrDeserializer<'de> {
         self.deserialize_any(visitor)
     }
 
+    // Keys are always strings, but allow maps with integer keys to be
+    // deserialized from keys which are valid integers.
+    deserialize_int_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+    }
+
     serde::forward_to_deserialize_any! {
-        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string seq
+        bool f32 f64 char str string seq
         bytes byte_buf map option unit newtype_struct
         ignored_any unit_struct tuple_struct tuple enum identifier
     }
@@ -774,6 +1772,7 @@ impl<'de> de::Deserializer<'de> for StrDeserializer<'de> {
 struct ValueDeserializer<'a> {
     value: Value<'a>,
     validate_struct_keys: bool,
+    tracker: Option<Tracker>,
 }
 
 impl<'a> ValueDeserializer<'a> {
@@ -781,6 +1780,7 @@ impl<'a> ValueDeserializer<'a> {
         ValueDeserializer {
             value,
             validate_struct_keys: false,
+            tracker: None,
         }
     }
 
@@ -788,6 +1788,14 @@ impl<'a> ValueDeserializer<'a> {
         self.validate_struct_keys = true;
         self
     }
+
+    fn with_tracker(mut self, tracker: Option<Tracker>) -> Self {
+        if let Some(ref tracker) = tracker {
+            tracker.record_span(self.value.start, self.value.end);
+        }
+        self.tracker = tracker;
+        self
+    }
 }
 
 impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
@@ -809,7 +1817,12 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
                 visited: false,
             }),
             E::Array(values) => {
-                let mut s = de::value::SeqDeserializer::new(values.into_iter());
+                let mut tracker = self.tracker;
+                let values = values.into_iter().map(|value| {
+                    ValueDeserializer::new(value)
+                        .with_tracker(tracker.as_mut().map(Tracker::element))
+                });
+                let mut s = de::value::SeqDeserializer::new(values);
                 let ret = visitor.visit_seq(&mut s)?;
                 s.end()?;
                 Ok(ret)
@@ -818,6 +1831,7 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
                 visitor.visit_map(InlineTableDeserializer {
                     values: values.into_iter(),
                     next_value: None,
+                    tracker: self.tracker,
                 })
             }
         };
@@ -885,7 +1899,7 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
             return visitor.visit_map(SpannedDeserializer {
                 phantom_data: PhantomData,
                 start: Some(start),
-                value: Some(self.value),
+                value: Some(ValueDeserializer::new(self.value).with_tracker(self.tracker)),
                 end: Some(end),
             });
         }
@@ -930,6 +1944,7 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
                     visitor.visit_enum(InlineTableDeserializer {
                         values: values.into_iter(),
                         next_value: None,
+                        tracker: None,
                     })
                 }
             }
@@ -954,10 +1969,41 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
         visitor.visit_newtype_struct(self)
     }
 
+    fn deserialize_ignored_any<V>(mut self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if let Some(tracker) = self.tracker.take() {
+            tracker.report();
+        }
+        self.deserialize_any(visitor)
+    }
+
+    // Unit structs are serialized as empty tables.
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.value.e {
+            E::InlineTable(ref values) | E::DottedTable(ref values) if values.is_empty() => {
+                visitor.visit_unit()
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
     serde::forward_to_deserialize_any! {
         bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string seq
         bytes byte_buf map unit identifier
-        ignored_any unit_struct tuple_struct tuple
+        tuple_struct tuple
+    }
+}
+
+impl<'de> de::IntoDeserializer<'de, Error> for ValueDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
     }
 }
 
@@ -1099,7 +2145,8 @@ impl<'de> de::EnumAccess<'de> for DottedTableDeserializer<'de> {
 
 struct InlineTableDeserializer<'a> {
     values: vec::IntoIter<TablePair<'a>>,
-    next_value: Option<Value<'a>>,
+    next_value: Option<(Cow<'a, str>, ValueDeserializer<'a>)>,
+    tracker: Option<Tracker>,
 }
 
 impl<'de> de::MapAccess<'de> for InlineTableDeserializer<'de> {
@@ -1113,7 +2160,11 @@ impl<'de> de::MapAccess<'de> for InlineTableDeserializer<'de> {
             Some(pair) => pair,
             None => return Ok(None),
         };
-        self.next_value = Some(value);
+        let tracker = self.tracker.as_ref().map(|u| u.child(&key.1));
+        self.next_value = Some((
+            key.1.clone(),
+            ValueDeserializer::new(value).with_tracker(tracker),
+        ));
         seed.deserialize(StrDeserializer::spanned(key)).map(Some)
     }
 
@@ -1121,8 +2172,11 @@ impl<'de> de::MapAccess<'de> for InlineTableDeserializer<'de> {
     where
         V: de::DeserializeSeed<'de>,
     {
-        let value = self.next_value.take().expect("Unable to read table values");
-        seed.deserialize(ValueDeserializer::new(value))
+        let (key, value) = self.next_value.take().expect("Unable to read table values");
+        seed.deserialize(value).map_err(|mut e| {
+            e.add_key_context(&key);
+            e
+        })
     }
 }
 
@@ -1267,11 +2321,26 @@ impl<'a> Deserializer<'a> {
     /// Creates a new deserializer which will be deserializing the string
     /// provided.
     pub fn new(input: &'a str) -> Deserializer<'a> {
+        Deserializer::with_offset(input, 0)
+    }
+
+    /// Creates a new deserializer for TOML which is embedded at byte offset
+    /// `base` of a larger document, such as the front matter of a page.
+    ///
+    /// The offsets reported by `Error::offset` are then relative to the
+    /// enclosing document. Line and column numbers reported by
+    /// `Error::line_col` remain relative to `input`, as the text before it
+    /// isn't known.
+    pub fn with_offset(input: &'a str, base: usize) -> Deserializer<'a> {
         Deserializer {
             tokens: Tokenizer::new(input),
             input,
             require_newline_after_table: true,
             allow_duplciate_after_longer_table: false,
+            strict_duplicates: false,
+            integer_to_float_fallback: false,
+            base,
+            tracker: None,
         }
     }
 
@@ -1280,7 +2349,11 @@ impl<'a> Deserializer<'a> {
     /// input stream is at the end or that it only has trailing
     /// whitespace/comments.
     pub fn end(&mut self) -> Result<(), Error> {
-        Ok(())
+        self.eat_blank_lines()?;
+        match self.peek()? {
+            Some((span, _)) => Err(self.error(span.start, ErrorKind::UnexpectedContent)),
+            None => Ok(()),
+        }
     }
 
     /// Historical versions of toml-rs accidentally allowed a newline after a
@@ -1303,6 +2376,97 @@ impl<'a> Deserializer<'a> {
         self.allow_duplciate_after_longer_table = allow;
     }
 
+    /// TOML allows a table which was implicitly created by a longer table
+    /// header to be defined later on, as in:
+    ///
+    /// ```toml,ignore
+    /// [a.b]
+    /// c = 1
+    ///
+    /// [a]
+    /// d = 2
+    /// ```
+    ///
+    /// This option can be set to `true` (the default is `false`) to reject
+    /// such documents, so that every table is opened by at most one header.
+    /// New elements of an array of tables may still define their own
+    /// subtables.
+    pub fn set_strict_duplicates(&mut self, strict: bool) {
+        self.strict_duplicates = strict;
+    }
+
+    /// Some TOML dialects accept two digit hexadecimal escapes such as
+    /// `"\x41"` in basic strings, which the TOML spec doesn't allow.
+    ///
+    /// This option can be set to `true` (the default is `false`) to accept
+    /// these escapes as an extension, each of them denoting the code point
+    /// `U+0000` to `U+00FF` given by its two hexadecimal digits.
+    pub fn set_hex_escapes(&mut self, allow: bool) {
+        self.tokens.set_hex_escapes(allow);
+    }
+
+    /// Integer literals which don't fit in an `i64`, such as
+    /// `18446744073709551615`, are rejected by default.
+    ///
+    /// This option can be set to `true` (the default is `false`) to parse
+    /// them as floats instead, which may lose precision. This applies to
+    /// hexadecimal, octal and binary literals as well.
+    pub fn set_integer_to_float_fallback(&mut self, fallback: bool) {
+        self.integer_to_float_fallback = fallback;
+    }
+
+    /// Parses the whole document without deserializing it, collecting every
+    /// error found instead of stopping at the first one.
+    ///
+    /// After an error the rest of the offending line is skipped, along with
+    /// any further lines of an array or inline table started on it, and
+    /// parsing resumes on the next line, so a document with several malformed values
+    /// reports one error for each of them. This is intended for tools such as
+    /// editors which want to surface all problems in a single pass.
+    pub fn parse_recover(&mut self) -> Result<(), Vec<Error>> {
+        let mut errors = Vec::new();
+        let mut header_keys = Vec::new();
+        let mut values = Vec::new();
+        let mut index = KeyIndex::new();
+        loop {
+            if let Err(e) = self.eat_blank_lines() {
+                errors.push(e);
+                self.tokens.skip_to_newline();
+                continue;
+            }
+            let start = self.tokens.current();
+            let result = match self.line() {
+                Ok(Some(Line::Table { mut header, .. })) => {
+                    header_keys.clear();
+                    values.clear();
+                    index.clear();
+                    loop {
+                        match header.next() {
+                            Ok(Some(part)) => header_keys.push(part),
+                            Ok(None) => break Ok(()),
+                            Err(e) => break Err(self.token_error(e)),
+                        }
+                    }
+                }
+                Ok(Some(Line::KeyValue(key, value))) => self
+                    .add_dotted_key(key, value, &mut values, &mut index)
+                    .map_err(|e| e.duplicate_key_context(&header_keys)),
+                Ok(None) => break,
+                Err(e) => Err(e.duplicate_key_context(&header_keys)),
+            };
+            if let Err(e) = result {
+                errors.push(e);
+                self.tokens.seek(start);
+                self.tokens.skip_statement();
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     fn tables(&mut self) -> Result<Vec<Table<'a>>, Error> {
         let mut tables = Vec::new();
         let mut cur_table = Table {
@@ -1311,8 +2475,12 @@ impl<'a> Deserializer<'a> {
             values: None,
             array: false,
         };
+        let mut index = KeyIndex::new();
 
-        while let Some(line) = self.line()? {
+        while let Some(line) = self
+            .line()
+            .map_err(|e| e.duplicate_key_context(&cur_table.header))?
+        {
             match line {
                 Line::Table {
                     at,
@@ -1328,6 +2496,7 @@ impl<'a> Deserializer<'a> {
                         values: Some(Vec::new()),
                         array,
                     };
+                    index.clear();
                     loop {
                         let part = header.next().map_err(|e| self.token_error(e));
                         match part? {
@@ -1335,12 +2504,16 @@ impl<'a> Deserializer<'a> {
                             None => break,
                         }
                     }
+                    if self.strict_duplicates && !array {
+                        self.check_implicit_table_reopened(&tables, &cur_table)?;
+                    }
                 }
                 Line::KeyValue(key, value) => {
                     if cur_table.values.is_none() {
                         cur_table.values = Some(Vec::new());
                     }
-                    self.add_dotted_key(key, value, cur_table.values.as_mut().unwrap())?;
+                    self.add_dotted_key(key, value, cur_table.values.as_mut().unwrap(), &mut index)
+                        .map_err(|e| e.duplicate_key_context(&cur_table.header))?;
                 }
             }
         }
@@ -1350,17 +2523,107 @@ impl<'a> Deserializer<'a> {
         Ok(tables)
     }
 
-    fn line(&mut self) -> Result<Option<Line<'a>>, Error> {
-        loop {
-            self.eat_whitespace()?;
-            if self.eat_comment()? {
-                continue;
+    /// Fails if the header of an array of tables names a key which holds an
+    /// array of values, such as `a` in:
+    ///
+    /// ```toml,ignore
+    /// a = [1, 2]
+    /// [[a]]
+    /// ```
+    ///
+    /// The value may be defined either before or after the array of tables.
+    fn check_arrays_of_tables(
+        &self,
+        tables: &[Table<'a>],
+        table_indices: &HashMap<Vec<Cow<'a, str>>, Vec<usize>>,
+    ) -> Result<(), Error> {
+        let position = |indices: &[usize], i: usize| match indices.binary_search(&i) {
+            Ok(pos) | Err(pos) => pos,
+        };
+
+        for (ti, table) in tables.iter().enumerate().filter(|(_, t)| t.array) {
+            let header = table.header.iter().map(|k| k.1.clone()).collect::<Vec<_>>();
+
+            // Tables belonging to other elements of an enclosing array of
+            // tables can't hold the key, so only look between the closest
+            // such elements.
+            let mut lo = 0;
+            let mut hi = tables.len();
+            for len in 1..header.len() {
+                let indices = match table_indices.get(&header[..len]) {
+                    Some(indices) => indices,
+                    None => continue,
+                };
+                let pos = position(indices, ti);
+                if let Some(&i) = indices[..pos].iter().rev().find(|&&i| tables[i].array) {
+                    lo = lo.max(i);
+                }
+                if let Some(&i) = indices[pos..].iter().find(|&&i| tables[i].array) {
+                    hi = hi.min(i);
+                }
             }
-            if self.eat(Token::Newline)? {
-                continue;
+
+            // Look for the key in every table which is a prefix of the
+            // header, following dotted keys for the rest of the header.
+            for len in 0..header.len() {
+                let indices = match table_indices.get(&header[..len]) {
+                    Some(indices) => indices,
+                    None => continue,
+                };
+                for &parent in indices[position(indices, lo)..].iter() {
+                    if parent >= hi {
+                        break;
+                    }
+                    let mut values = match tables[parent].values {
+                        Some(ref values) => values,
+                        None => continue,
+                    };
+                    for (i, part) in header[len..].iter().enumerate() {
+                        let value = match values.iter().find(|(k, _)| k.1 == *part) {
+                            Some((_, value)) => value,
+                            None => break,
+                        };
+                        match value.e {
+                            E::DottedTable(ref dotted) => values = dotted,
+                            E::Array(_) if len + i + 1 == header.len() => {
+                                let name = header.join(".");
+                                let kind = ErrorKind::ValueArrayAsArrayOfTables(name);
+                                return Err(self.error(table.at.max(value.start), kind));
+                            }
+                            _ => break,
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if `table` names a table which was implicitly created by one of
+    /// the longer headers in `tables`.
+    fn check_implicit_table_reopened(
+        &self,
+        tables: &[Table<'a>],
+        table: &Table<'a>,
+    ) -> Result<(), Error> {
+        let header = &table.header;
+        for prev in tables.iter().rev() {
+            let len = prev.header.len();
+            // Tables before a new element of an enclosing array of tables
+            // belong to a previous element.
+            if prev.array && len <= header.len() && headers_equal(&prev.header, &header[..len]) {
+                break;
+            }
+            if len > header.len() && headers_equal(&prev.header[..header.len()], header) {
+                let name = header.iter().map(|k| &*k.1).collect::<Vec<_>>().join(".");
+                return Err(self.error(table.at, ErrorKind::DuplicateTable(name)));
             }
-            break;
         }
+        Ok(())
+    }
+
+    fn line(&mut self) -> Result<Option<Line<'a>>, Error> {
+        self.eat_blank_lines()?;
 
         match self.peek()? {
             Some((_, Token::LeftBracket)) => self.table_header().map(Some),
@@ -1404,7 +2667,7 @@ impl<'a> Deserializer<'a> {
         self.expect(Token::Equals)?;
         self.eat_whitespace()?;
 
-        let value = self.value()?;
+        let value = self.value().map_err(|e| e.duplicate_key_context(&key))?;
         self.eat_whitespace()?;
         if !self.eat_comment()? {
             self.eat_newline_or_eof()?;
@@ -1549,17 +2812,13 @@ impl<'a> Deserializer<'a> {
     }
 
     fn number(&mut self, Span { start, end }: Span, s: &'a str) -> Result<Value<'a>, Error> {
-        let to_integer = |f| Value {
-            e: E::Integer(f),
-            start,
-            end,
-        };
+        let to_integer = |e| Value { e, start, end };
         if s.starts_with("0x") {
-            self.integer(&s[2..], 16).map(to_integer)
+            self.integer_or_float(&s[2..], 16).map(to_integer)
         } else if s.starts_with("0o") {
-            self.integer(&s[2..], 8).map(to_integer)
+            self.integer_or_float(&s[2..], 8).map(to_integer)
         } else if s.starts_with("0b") {
-            self.integer(&s[2..], 2).map(to_integer)
+            self.integer_or_float(&s[2..], 2).map(to_integer)
         } else if s.contains('e') || s.contains('E') {
             self.float(s, None).map(|f| Value {
                 e: E::Float(f),
@@ -1569,7 +2828,7 @@ impl<'a> Deserializer<'a> {
         } else if self.eat(Token::Period)? {
             let at = self.tokens.current();
             match self.next()? {
-                Some((Span { start, end }, Token::Keylike(after))) => {
+                Some((Span { end, .. }, Token::Keylike(after))) => {
                     self.float(s, Some(after)).map(|f| Value {
                         e: E::Float(f),
                         start,
@@ -1603,15 +2862,49 @@ impl<'a> Deserializer<'a> {
                 end,
             })
         } else {
-            self.integer(s, 10).map(to_integer)
+            self.integer_or_float(s, 10).map(to_integer)
+        }
+    }
+
+    /// Parses an integer, falling back to a float on overflow if
+    /// `integer_to_float_fallback` is set.
+    fn integer_or_float(&self, s: &'a str, radix: u32) -> Result<E<'a>, Error> {
+        match self.integer(s, radix) {
+            Ok(i) => Ok(E::Integer(i)),
+            Err(ref e)
+                if self.integer_to_float_fallback && e.inner.kind == ErrorKind::IntegerOverflow =>
+            {
+                let digits = s.replace("_", "");
+                let f = if radix == 10 {
+                    digits.parse().unwrap()
+                } else {
+                    digits.chars().fold(0.0, |f, c| {
+                        f * f64::from(radix) + f64::from(c.to_digit(radix).unwrap())
+                    })
+                };
+                Ok(E::Float(f))
+            }
+            Err(e) => Err(e),
         }
     }
 
     fn number_leading_plus(&mut self, Span { start, .. }: Span) -> Result<Value<'a>, Error> {
         let start_token = self.tokens.current();
         match self.next()? {
-            Some((Span { end, .. }, Token::Keylike(s))) => self.number(Span { start, end }, s),
-            _ => Err(self.error(start_token, ErrorKind::NumberInvalid)),
+            Some((Span { end, .. }, Token::Keylike(s)))
+                if s.starts_with(|c: char| c.is_ascii_digit()) || s == "inf" || s == "nan" =>
+            {
+                self.number(Span { start, end }, s)
+            }
+            // A `+` is only ever valid right before a number, so report what
+            // was found in its place rather than a generic invalid number.
+            token => Err(self.error(
+                start_token,
+                ErrorKind::Wanted {
+                    expected: "a number after `+`",
+                    found: token.map(|(_, t)| t.describe()).unwrap_or("eof"),
+                },
+            )),
         }
     }
 
@@ -1623,8 +2916,15 @@ impl<'a> Deserializer<'a> {
         if suffix != "" {
             return Err(self.error(start, ErrorKind::NumberInvalid));
         }
-        i64::from_str_radix(&prefix.replace("_", "").trim_start_matches('+'), radix)
-            .map_err(|_e| self.error(start, ErrorKind::NumberInvalid))
+        i64::from_str_radix(&prefix.replace("_", "").trim_start_matches('+'), radix).map_err(|e| {
+            // The digits have been validated above, so anything but an
+            // overflow is still an invalid number.
+            let kind = match *e.kind() {
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => ErrorKind::IntegerOverflow,
+                _ => ErrorKind::NumberInvalid,
+            };
+            self.error(start, kind)
+        })
     }
 
     fn parse_integer(
@@ -1796,6 +3096,7 @@ impl<'a> Deserializer<'a> {
     // great to defer parsing everything until later.
     fn inline_table(&mut self) -> Result<(Span, Vec<TablePair<'a>>), Error> {
         let mut ret = Vec::new();
+        let mut index = KeyIndex::new();
         self.eat_whitespace()?;
         if let Some(span) = self.eat_spanned(Token::RightBrace)? {
             return Ok((span, ret));
@@ -1805,8 +3106,8 @@ impl<'a> Deserializer<'a> {
             self.eat_whitespace()?;
             self.expect(Token::Equals)?;
             self.eat_whitespace()?;
-            let value = self.value()?;
-            self.add_dotted_key(key, value, &mut ret)?;
+            let value = self.value().map_err(|e| e.duplicate_key_context(&key))?;
+            self.add_dotted_key(key, value, &mut ret, &mut index)?;
 
             self.eat_whitespace()?;
             if let Some(span) = self.eat_spanned(Token::RightBrace)? {
@@ -1877,7 +3178,38 @@ impl<'a> Deserializer<'a> {
     ///                `vec![Cow::Borrowed("part"), Cow::Borrowed("one")].`
     /// * `value`: The parsed value.
     /// * `values`: The `Vec` to store the value in.
+    /// * `index`: The key paths already defined in `values`.
     fn add_dotted_key(
+        &self,
+        key_parts: Vec<(Span, Cow<'a, str>)>,
+        value: Value<'a>,
+        values: &mut Vec<TablePair<'a>>,
+        index: &mut KeyIndex<'a>,
+    ) -> Result<(), Error> {
+        let path: Vec<_> = key_parts.iter().map(|k| k.1.clone()).collect();
+        if let Some(&first) = index.get(&path) {
+            let (first_line, _) = self.to_linecol(first);
+            let (key, parents) = key_parts.split_last().unwrap();
+            let err = self.error(
+                key.0.start,
+                ErrorKind::DuplicateKey {
+                    key: key.1.to_string(),
+                    first_line,
+                },
+            );
+            return Err(err.duplicate_key_context(parents));
+        }
+        let starts: Vec<_> = key_parts.iter().map(|k| k.0.start).collect();
+        self.insert_dotted_key(key_parts, value, values)?;
+        for (i, start) in starts.into_iter().enumerate() {
+            if !index.contains_key(&path[..=i]) {
+                index.insert(path[..=i].to_vec(), start);
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_dotted_key(
         &self,
         mut key_parts: Vec<(Span, Cow<'a, str>)>,
         value: Value<'a>,
@@ -1896,7 +3228,7 @@ impl<'a> Deserializer<'a> {
                     ..
                 },
             )) => {
-                return self.add_dotted_key(key_parts, value, v);
+                return self.insert_dotted_key(key_parts, value, v);
             }
             Some(&mut (_, Value { start, .. })) => {
                 return Err(self.error(start, ErrorKind::DottedKeyInvalidType));
@@ -1919,11 +3251,24 @@ impl<'a> Deserializer<'a> {
             },
         ) = values[last_i]
         {
-            self.add_dotted_key(key_parts, value, v)?;
+            self.insert_dotted_key(key_parts, value, v)?;
         }
         Ok(())
     }
 
+    fn eat_blank_lines(&mut self) -> Result<(), Error> {
+        loop {
+            self.eat_whitespace()?;
+            if self.eat_comment()? {
+                continue;
+            }
+            if self.eat(Token::Newline)? {
+                continue;
+            }
+            return Ok(());
+        }
+    }
+
     fn eat_whitespace(&mut self) -> Result<(), Error> {
         self.tokens
             .eat_whitespace()
@@ -1999,7 +3344,7 @@ impl<'a> Deserializer<'a> {
 
     fn error(&self, at: usize, kind: ErrorKind) -> Error {
         let mut err = Error::from_kind(Some(at), kind);
-        err.fix_linecol(|at| self.to_linecol(at));
+        err.fix_linecol(self.base, |at| self.to_linecol(at));
         err
     }
 
@@ -2029,6 +3374,20 @@ impl Error {
         self.inner.line.map(|line| (line, self.inner.col))
     }
 
+    /// Produces the byte offset of the position of the error if available
+    ///
+    /// For a deserializer created with `Deserializer::with_offset` the offset
+    /// includes the base given there.
+    pub fn offset(&self) -> Option<usize> {
+        self.inner.at.map(|at| self.inner.base + at)
+    }
+
+    /// Returns the kind of this error, for matching on it rather than on its
+    /// message.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.inner.kind
+    }
+
     fn from_kind(at: Option<usize>, kind: ErrorKind) -> Error {
         Error {
             inner: Box::new(ErrorInner {
@@ -2036,6 +3395,7 @@ impl Error {
                 line: None,
                 col: 0,
                 at,
+                base: 0,
                 message: String::new(),
                 key: Vec::new(),
             }),
@@ -2049,6 +3409,7 @@ impl Error {
                 line: None,
                 col: 0,
                 at,
+                base: 0,
                 message: s,
                 key: Vec::new(),
             }),
@@ -2059,6 +3420,17 @@ impl Error {
         self.inner.key.insert(0, key.to_string());
     }
 
+    /// Prepends `keys` to the key path of a duplicate key error. These are
+    /// found while parsing, before any key context is added by deserializing.
+    fn duplicate_key_context(mut self, keys: &[(Span, Cow<'_, str>)]) -> Error {
+        if let ErrorKind::DuplicateKey { .. } = self.inner.kind {
+            for key in keys.iter().rev() {
+                self.add_key_context(&key.1);
+            }
+        }
+        self
+    }
+
     fn fix_offset<F>(&mut self, f: F)
     where
         F: FnOnce() -> Option<usize>,
@@ -2070,7 +3442,7 @@ impl Error {
         }
     }
 
-    fn fix_linecol<F>(&mut self, f: F)
+    fn fix_linecol<F>(&mut self, base: usize, f: F)
     where
         F: FnOnce(usize) -> (usize, usize),
     {
@@ -2078,6 +3450,7 @@ impl Error {
             let (line, col) = f(at);
             self.inner.line = Some(line);
             self.inner.col = col;
+            self.inner.base = base;
         }
     }
 }
@@ -2120,11 +3493,19 @@ impl fmt::Display for Error {
                 write!(f, "expected {}, found {}", expected, found)?
             }
             ErrorKind::NumberInvalid => "invalid number".fmt(f)?,
+            ErrorKind::IntegerOverflow => {
+                "integer literal out of range for a 64-bit signed integer".fmt(f)?
+            }
             ErrorKind::DateInvalid => "invalid date".fmt(f)?,
             ErrorKind::DuplicateTable(ref s) => {
                 write!(f, "redefinition of table `{}`", s)?;
             }
             ErrorKind::RedefineAsArray => "table redefined as array".fmt(f)?,
+            ErrorKind::ValueArrayAsArrayOfTables(ref key) => write!(
+                f,
+                "`{}` is already an array of values, cannot use as array of tables",
+                key
+            )?,
             ErrorKind::EmptyTableKey => "empty table key found".fmt(f)?,
             ErrorKind::MultilineStringKey => "multiline strings are not allowed for key".fmt(f)?,
             ErrorKind::Custom => self.inner.message.fmt(f)?,
@@ -2137,6 +3518,8 @@ impl fmt::Display for Error {
             ErrorKind::DottedKeyInvalidType => {
                 "dotted key attempted to extend non-table type".fmt(f)?
             }
+            ErrorKind::UnexpectedContent => "unexpected content after end of document".fmt(f)?,
+            ErrorKind::DuplicateKey { ref key, .. } => write!(f, "duplicate key: `{}`", key)?,
             ErrorKind::UnexpectedKeys {
                 ref keys,
                 available,
@@ -2149,7 +3532,6 @@ impl fmt::Display for Error {
                 f,
                 "invalid TOML value, did you mean to use a quoted string?"
             )?,
-            ErrorKind::__Nonexhaustive => panic!(),
         }
 
         if !self.inner.key.is_empty() {
@@ -2163,6 +3545,10 @@ impl fmt::Display for Error {
             write!(f, "`")?;
         }
 
+        if let ErrorKind::DuplicateKey { first_line, .. } = self.inner.kind {
+            write!(f, " (previously defined at line {})", first_line + 1)?;
+        }
+
         if let Some(line) = self.inner.line {
             write!(f, " at line {} column {}", line + 1, self.inner.col + 1)?;
         }
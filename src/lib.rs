@@ -158,13 +158,18 @@ pub mod value;
 #[doc(no_inline)]
 pub use crate::value::Value;
 mod datetime;
+pub mod spanned_value;
+pub mod validate;
 
 pub mod ser;
 #[doc(no_inline)]
-pub use crate::ser::{to_string, to_string_pretty, to_vec, Serializer};
+pub use crate::ser::{to_string, to_string_pretty, to_string_with, to_vec, Serializer};
 pub mod de;
 #[doc(no_inline)]
-pub use crate::de::{from_slice, from_str, Deserializer};
+pub use crate::de::{
+    from_reader, from_slice, from_str, from_str_reporting_unused, from_str_spanned,
+    from_str_with_spans, parse_path, unescape, Deserializer,
+};
 mod tokens;
 
 #[doc(hidden)]
@@ -26,6 +26,8 @@
 //! ```
 
 use std::cell::Cell;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::error;
 use std::fmt::{self, Write};
 use std::marker;
@@ -93,6 +95,35 @@ where
     Ok(dst)
 }
 
+/// Serialize the given data structure as a String of TOML, formatted
+/// according to `opts`.
+///
+/// This is equivalent to serializing with a `Serializer` configured through
+/// its builder methods, see `SerializeOptions` for the available options.
+///
+/// # Examples
+///
+/// ```
+/// use toml::ser::{LineEnding, SerializeOptions};
+///
+/// let opts = SerializeOptions::pretty().line_ending(LineEnding::Crlf);
+/// let value: toml::Value = "a = [1, 2]".parse().unwrap();
+/// let toml = toml::to_string_with(&value, &opts).unwrap();
+/// assert_eq!(toml, "a = [\r\n    1,\r\n    2,\r\n]\r\n");
+/// ```
+pub fn to_string_with<T>(value: &T, opts: &SerializeOptions) -> Result<String, Error>
+where
+    T: ?Sized + ser::Serialize,
+{
+    let mut dst = String::with_capacity(128);
+    {
+        let mut serializer = Serializer::new(&mut dst);
+        opts.apply(&mut serializer);
+        value.serialize(&mut serializer)?;
+    }
+    Ok(dst)
+}
+
 /// Serialize the given data structure as a "pretty" String of TOML.
 ///
 /// This is identical to `to_string` except the output string has a more
@@ -124,14 +155,29 @@ pub enum Error {
     #[doc(hidden)]
     KeyNewline,
 
-    /// An array had to be homogeneous, but now it is allowed to be heterogeneous.
-    #[doc(hidden)]
-    ArrayMixedType,
+    /// An array contained both tables and other values. Arrays of tables are
+    /// emitted as `[[...]]` sections, which other values can't be mixed with.
+    ArrayMixedType {
+        /// The dotted key path of the array
+        key: String,
+        /// The kind of the first element of the array, `"table"` or `"value"`
+        expected: &'static str,
+        /// The kind of the offending element
+        found: &'static str,
+    },
 
     /// All values in a TOML table must be emitted before further tables are
     /// emitted. If a value is emitted *after* a table then this error is
     /// generated.
-    ValueAfterTable,
+    ValueAfterTable {
+        /// The dotted key path of the offending value
+        key: String,
+    },
+
+    /// A sequence of tables was serialized as the document itself. TOML
+    /// documents must have a table at the root, so an array of tables can
+    /// only be serialized as the value of a key.
+    ArrayOfTablesAtRoot,
 
     /// A serialized date was invalid.
     DateInvalid,
@@ -142,19 +188,205 @@ pub enum Error {
     /// None was attempted to be serialized, but it's not supported.
     UnsupportedNone,
 
+    /// An array contained a None value. TOML has no null, so unless
+    /// `Serializer::skip_none` is enabled these can't be serialized.
+    NoneInArray,
+
     /// A custom error which could be generated when serializing a particular
-    /// type.
+    /// type. If it was raised by a value nested in a table, the message ends
+    /// with the dotted key path of that value.
     Custom(String),
 
     #[doc(hidden)]
     __Nonexhaustive,
 }
 
+/// Formatting options for `to_string_with`.
+///
+/// Each option is set by the builder method of the same name, which
+/// corresponds to the `Serializer` builder method of that name. The default
+/// options give the output of `to_string`, and `SerializeOptions::pretty`
+/// that of `to_string_pretty`.
+#[derive(Debug, Clone)]
+pub struct SerializeOptions {
+    pretty_string: bool,
+    pretty_string_literal: bool,
+    pretty_array: bool,
+    pretty_array_indent: usize,
+    pretty_array_trailing_comma: bool,
+    pretty_array_threshold: usize,
+    always_quote_keys: bool,
+    compact_tables: bool,
+    float_precision: Option<usize>,
+    skip_none: bool,
+    prefer_literal_strings: bool,
+    empty_table_as_inline: bool,
+    stringify_int_keys: bool,
+    line_ending: LineEnding,
+    key_order: Option<KeyOrder>,
+}
+
+impl SerializeOptions {
+    /// Returns the options used by `to_string_pretty`, see
+    /// `Serializer::pretty`.
+    pub fn pretty() -> SerializeOptions {
+        SerializeOptions::default()
+            .pretty_string(true)
+            .pretty_array(true)
+    }
+
+    /// Whether to use pretty strings. Defaults to `false`.
+    pub fn pretty_string(mut self, value: bool) -> Self {
+        self.pretty_string = value;
+        self
+    }
+
+    /// Whether pretty strings may be literal strings, only used along with
+    /// `pretty_string`. Defaults to `true`.
+    pub fn pretty_string_literal(mut self, value: bool) -> Self {
+        self.pretty_string_literal = value;
+        self
+    }
+
+    /// Whether to use pretty arrays. Defaults to `false`.
+    pub fn pretty_array(mut self, value: bool) -> Self {
+        self.pretty_array = value;
+        self
+    }
+
+    /// Indentation of the elements of pretty arrays. Defaults to 4.
+    pub fn pretty_array_indent(mut self, value: usize) -> Self {
+        self.pretty_array_indent = value;
+        self
+    }
+
+    /// Whether pretty arrays have a trailing comma. Defaults to `true`.
+    pub fn pretty_array_trailing_comma(mut self, value: bool) -> Self {
+        self.pretty_array_trailing_comma = value;
+        self
+    }
+
+    /// Pretty arrays with at most this many elements stay on a single line.
+    /// Defaults to 1.
+    pub fn pretty_array_threshold(mut self, value: usize) -> Self {
+        self.pretty_array_threshold = value;
+        self
+    }
+
+    /// Whether to quote all keys. Defaults to `false`.
+    pub fn always_quote_keys(mut self, value: bool) -> Self {
+        self.always_quote_keys = value;
+        self
+    }
+
+    /// Whether to omit the blank lines between tables. Defaults to `false`.
+    pub fn compact_tables(mut self, value: bool) -> Self {
+        self.compact_tables = value;
+        self
+    }
+
+    /// Maximum number of significant digits of floats, `None` to use as many
+    /// as needed. Defaults to `None`.
+    pub fn float_precision(mut self, value: Option<usize>) -> Self {
+        self.float_precision = value;
+        self
+    }
+
+    /// Whether to skip `None` elements of arrays. Defaults to `false`.
+    pub fn skip_none(mut self, value: bool) -> Self {
+        self.skip_none = value;
+        self
+    }
+
+    /// Whether to use literal strings for values containing backslashes.
+    /// Defaults to `false`.
+    pub fn prefer_literal_strings(mut self, value: bool) -> Self {
+        self.prefer_literal_strings = value;
+        self
+    }
+
+    /// Whether to emit empty tables as `{}`. Defaults to `false`.
+    pub fn empty_table_as_inline(mut self, value: bool) -> Self {
+        self.empty_table_as_inline = value;
+        self
+    }
+
+    /// Whether to accept integer map keys. Defaults to `false`.
+    pub fn stringify_int_keys(mut self, value: bool) -> Self {
+        self.stringify_int_keys = value;
+        self
+    }
+
+    /// The line ending to use. Defaults to `LineEnding::Lf`.
+    pub fn line_ending(mut self, value: LineEnding) -> Self {
+        self.line_ending = value;
+        self
+    }
+
+    /// Comparator for the keys of each table. By default keys are emitted in
+    /// the order in which they are serialized.
+    pub fn sort_keys_by<F>(mut self, cmp: F) -> Self
+    where
+        F: Fn(&str, &str) -> Ordering + 'static,
+    {
+        self.key_order = Some(KeyOrder(Rc::new(cmp)));
+        self
+    }
+
+    fn apply(&self, serializer: &mut Serializer<'_>) {
+        if self.pretty_string {
+            serializer.pretty_string_literal(self.pretty_string_literal);
+        }
+        if self.pretty_array {
+            serializer
+                .pretty_array_indent(self.pretty_array_indent)
+                .pretty_array_trailing_comma(self.pretty_array_trailing_comma)
+                .pretty_array_threshold(self.pretty_array_threshold);
+        }
+        serializer
+            .always_quote_keys(self.always_quote_keys)
+            .compact_tables(self.compact_tables)
+            .float_precision(self.float_precision)
+            .skip_none(self.skip_none)
+            .prefer_literal_strings(self.prefer_literal_strings)
+            .empty_table_as_inline(self.empty_table_as_inline)
+            .stringify_int_keys(self.stringify_int_keys)
+            .line_ending(self.line_ending);
+        if let Some(ref key_order) = self.key_order {
+            Rc::get_mut(&mut serializer.settings).unwrap().key_order = Some(key_order.clone());
+        }
+    }
+}
+
+impl Default for SerializeOptions {
+    fn default() -> SerializeOptions {
+        SerializeOptions {
+            pretty_string: false,
+            pretty_string_literal: true,
+            pretty_array: false,
+            pretty_array_indent: 4,
+            pretty_array_trailing_comma: true,
+            pretty_array_threshold: 1,
+            always_quote_keys: false,
+            compact_tables: false,
+            float_precision: None,
+            skip_none: false,
+            prefer_literal_strings: false,
+            empty_table_as_inline: false,
+            stringify_int_keys: false,
+            line_ending: LineEnding::Lf,
+            key_order: None,
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 /// Internal place for holding array settings
 struct ArraySettings {
     indent: usize,
     trailing_comma: bool,
+    /// Arrays with at most this many elements stay on a single line
+    threshold: usize,
 }
 
 impl ArraySettings {
@@ -162,6 +394,7 @@ impl ArraySettings {
         ArraySettings {
             indent: 4,
             trailing_comma: true,
+            threshold: 1,
         }
     }
 }
@@ -179,11 +412,60 @@ impl StringSettings {
     }
 }
 
+/// The line ending emitted by a `Serializer`, see `Serializer::line_ending`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Unix line endings, `\n` (the default)
+    Lf,
+    /// Windows line endings, `\r\n`
+    Crlf,
+}
+
+// Deriving `Default` for an enum with `#[default]` needs Rust 1.62, which is
+// newer than the compilers this crate supports.
+#[allow(clippy::derivable_impls)]
+impl Default for LineEnding {
+    fn default() -> LineEnding {
+        LineEnding::Lf
+    }
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 /// Internal struct for holding serialization settings
 struct Settings {
     array: Option<ArraySettings>,
     string: Option<StringSettings>,
+    always_quote_keys: bool,
+    compact_tables: bool,
+    float_precision: Option<usize>,
+    skip_none: bool,
+    prefer_literal_strings: bool,
+    key_order: Option<KeyOrder>,
+    line_ending: LineEnding,
+    empty_table_as_inline: bool,
+    stringify_int_keys: bool,
+}
+
+/// Comparator used to order the keys of every table, see
+/// `Serializer::sort_keys_by`
+#[derive(Clone)]
+struct KeyOrder(Rc<KeyCmp>);
+
+type KeyCmp = dyn Fn(&str, &str) -> Ordering;
+
+impl fmt::Debug for KeyOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KeyOrder")
+    }
 }
 
 /// Serialization implementation for TOML.
@@ -201,12 +483,21 @@ pub struct Serializer<'a> {
     settings: Rc<Settings>,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 enum ArrayState {
     Started,
     StartedAsATable,
 }
 
+impl ArrayState {
+    fn describe(self) -> &'static str {
+        match self {
+            ArrayState::Started => "value",
+            ArrayState::StartedAsATable => "table",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 enum State<'a> {
     Table {
@@ -240,6 +531,11 @@ pub enum SerializeTable<'a, 'b> {
         key: String,
         first: Cell<bool>,
         table_emitted: Cell<bool>,
+        /// The key, rank and output of each entry, for maps of unknown
+        /// length such as structs with `#[serde(flatten)]` fields, so that
+        /// values can be emitted before tables. Every table is buffered this
+        /// way when a key order has been set with `Serializer::sort_keys_by`.
+        buffer: Option<Vec<(String, u8, String)>>,
     },
 }
 
@@ -271,6 +567,15 @@ impl<'a> Serializer<'a> {
             settings: Rc::new(Settings {
                 array: Some(ArraySettings::pretty()),
                 string: Some(StringSettings::pretty()),
+                always_quote_keys: false,
+                compact_tables: false,
+                float_precision: None,
+                skip_none: false,
+                prefer_literal_strings: false,
+                key_order: None,
+                line_ending: LineEnding::Lf,
+                empty_table_as_inline: false,
+                stringify_int_keys: false,
             }),
         }
     }
@@ -359,6 +664,8 @@ impl<'a> Serializer<'a> {
     ///   than 4.
     /// - `Serializer::pretty_array_trailing_comma`: enable/disable the trailing
     ///   comma on the last item.
+    /// - `Serializer::pretty_array_threshold`: keep short arrays on a single
+    ///   line.
     ///
     /// # Examples
     ///
@@ -423,11 +730,202 @@ impl<'a> Serializer<'a> {
         self
     }
 
+    /// Set the number of elements up to which pretty arrays are still
+    /// emitted on a single line
+    ///
+    /// Arrays with more than `value` elements will have each item on their
+    /// own line. The default is 1, so only empty and single element arrays
+    /// are kept on one line.
+    ///
+    /// See `Serializer::pretty_array` for more details.
+    pub fn pretty_array_threshold(&mut self, value: usize) -> &mut Self {
+        let use_default = if let Some(ref mut a) = Rc::get_mut(&mut self.settings).unwrap().array {
+            a.threshold = value;
+            false
+        } else {
+            true
+        };
+
+        if use_default {
+            let mut array = ArraySettings::pretty();
+            array.threshold = value;
+            Rc::get_mut(&mut self.settings).unwrap().array = Some(array);
+        }
+        self
+    }
+
+    /// Enable or Disable quoting of all keys
+    ///
+    /// If enabled, every key will be emitted as a quoted string, even if it
+    /// could be written as a bare key. This gives uniform output, which can be
+    /// useful for keeping diffs of generated files stable.
+    ///
+    /// # Examples
+    ///
+    /// Instead of:
+    ///
+    /// ```toml,ignore
+    /// [dependencies]
+    /// serde = "1.0"
+    /// ```
+    ///
+    /// You will have:
+    ///
+    /// ```toml,ignore
+    /// ["dependencies"]
+    /// "serde" = "1.0"
+    /// ```
+    pub fn always_quote_keys(&mut self, value: bool) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().always_quote_keys = value;
+        self
+    }
+
+    /// Enable or Disable blank lines between tables
+    ///
+    /// If enabled, table headers directly follow the previous table instead
+    /// of being separated from it by a blank line.
+    pub fn compact_tables(&mut self, value: bool) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().compact_tables = value;
+        self
+    }
+
+    /// Set the maximum number of significant digits used for floats
+    ///
+    /// Floats are rounded to the given number of significant digits before
+    /// being emitted, so for example with a precision of 3 the value
+    /// `0.30000000000000004` is written as `0.3` and `1234.5` as `1230.0`.
+    /// `None`, the default, writes floats with as many digits as are needed to
+    /// represent them exactly.
+    pub fn float_precision(&mut self, digits: Option<usize>) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().float_precision = digits;
+        self
+    }
+
+    /// Enable or Disable skipping of `None` values in arrays
+    ///
+    /// `None` values of struct fields and map entries are always omitted
+    /// along with their key. Arrays, however, can't contain a missing value,
+    /// so by default a `None` element produces `Error::NoneInArray`. If
+    /// enabled, such elements are dropped from the array instead.
+    ///
+    /// This only affects the elements of arrays: a field of type
+    /// `Option<Vec<T>>` is omitted entirely when it is `None`, whereas the
+    /// `None` elements of a `Vec<Option<T>>` are governed by this setting.
+    pub fn skip_none(&mut self, value: bool) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().skip_none = value;
+        self
+    }
+
+    /// Enable or Disable literal strings for values containing backslashes
+    ///
+    /// If enabled, string values which contain backslashes are emitted as
+    /// literal strings when that is possible, that is when they contain no
+    /// single quotes, newlines or other control characters. This is mostly
+    /// useful for Windows paths, and has no effect with `pretty_string`,
+    /// which already prefers literal strings.
+    ///
+    /// # Examples
+    ///
+    /// Instead of:
+    ///
+    /// ```toml,ignore
+    /// path = "C:\\Users\\toml"
+    /// ```
+    ///
+    /// You will have:
+    ///
+    /// ```toml,ignore
+    /// path = 'C:\Users\toml'
+    /// ```
+    pub fn prefer_literal_strings(&mut self, value: bool) -> &mut Self {
+        Rc::get_mut(&mut self.settings)
+            .unwrap()
+            .prefer_literal_strings = value;
+        self
+    }
+
+    /// Sort the keys of every table with the given comparator
+    ///
+    /// By default keys are emitted in the order they are produced by the
+    /// value being serialized, which is declaration order for structs and
+    /// iteration order for maps. With a comparator set, the entries of each
+    /// table are collected and sorted before being emitted, for example to
+    /// put a `name` key first and order the remaining keys alphabetically:
+    ///
+    /// ```
+    /// # let mut dst = String::new();
+    /// # let mut serializer = toml::Serializer::new(&mut dst);
+    /// serializer.sort_keys_by(|a, b| (a != "name", a).cmp(&(b != "name", b)));
+    /// ```
+    ///
+    /// The comparator only decides the order of keys within each group: plain
+    /// values are still emitted before arrays of tables, which in turn come
+    /// before tables, as TOML requires.
+    pub fn sort_keys_by<F>(&mut self, cmp: F) -> &mut Self
+    where
+        F: Fn(&str, &str) -> Ordering + 'static,
+    {
+        Rc::get_mut(&mut self.settings).unwrap().key_order = Some(KeyOrder(Rc::new(cmp)));
+        self
+    }
+
+    /// Enable or Disable inline empty tables
+    ///
+    /// By default an empty table is emitted as a table header on its own.
+    /// If enabled, it is emitted as an empty inline table instead, much like
+    /// an empty array. That is, you will have:
+    ///
+    /// ```toml,ignore
+    /// features = {}
+    /// ```
+    ///
+    /// Instead of:
+    ///
+    /// ```toml,ignore
+    /// [features]
+    /// ```
+    ///
+    /// As with any other value, an inline table can't follow a table within
+    /// its parent table, so an empty table which comes after other tables is
+    /// still emitted as a header.
+    pub fn empty_table_as_inline(&mut self, value: bool) -> &mut Self {
+        Rc::get_mut(&mut self.settings)
+            .unwrap()
+            .empty_table_as_inline = value;
+        self
+    }
+
+    /// Enable or Disable integer map keys
+    ///
+    /// TOML keys are always strings, so by default serializing a map with
+    /// integer keys, such as a `HashMap<u32, T>`, fails with
+    /// `Error::KeyNotString`. If enabled, integer keys are written as their
+    /// decimal representation instead, which serde can deserialize back into
+    /// integer keys.
+    pub fn stringify_int_keys(&mut self, value: bool) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().stringify_int_keys = value;
+        self
+    }
+
+    /// Set the line ending used in the output
+    ///
+    /// Lines are terminated with `\n` by default. Tooling targeting Windows
+    /// may prefer `LineEnding::Crlf`, which terminates every line with
+    /// `\r\n` instead, including the lines of multi-line strings.
+    pub fn line_ending(&mut self, ending: LineEnding) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().line_ending = ending;
+        self
+    }
+
+    fn newline(&mut self) {
+        self.dst.push_str(self.settings.line_ending.as_str());
+    }
+
     fn display<T: fmt::Display>(&mut self, t: T, type_: ArrayState) -> Result<(), Error> {
         self.emit_key(type_)?;
         write!(self.dst, "{}", t).map_err(ser::Error::custom)?;
         if let State::Table { .. } = self.state {
-            self.dst.push_str("\n");
+            self.newline();
         }
         Ok(())
     }
@@ -461,7 +959,9 @@ impl<'a> Serializer<'a> {
                 key,
             } => {
                 if table_emitted.get() {
-                    return Err(Error::ValueAfterTable);
+                    return Err(Error::ValueAfterTable {
+                        key: key_path(state),
+                    });
                 }
                 if first.get() {
                     self.emit_table_header(parent)?;
@@ -476,19 +976,15 @@ impl<'a> Serializer<'a> {
 
     fn emit_array(&mut self, first: &Cell<bool>, len: Option<usize>) -> Result<(), Error> {
         match (len, &self.settings.array) {
-            (Some(0..=1), _) | (_, &None) => {
-                if first.get() {
-                    self.dst.push_str("[")
-                } else {
-                    self.dst.push_str(", ")
-                }
-            }
+            (Some(0..=1), _) | (_, &None) => self.emit_array_inline(first),
+            (Some(len), Some(a)) if len <= a.threshold => self.emit_array_inline(first),
             (_, &Some(ref a)) => {
                 if first.get() {
-                    self.dst.push_str("[\n")
+                    self.dst.push('[')
                 } else {
-                    self.dst.push_str(",\n")
+                    self.dst.push(',')
                 }
+                self.dst.push_str(self.settings.line_ending.as_str());
                 for _ in 0..a.indent {
                     self.dst.push_str(" ");
                 }
@@ -497,19 +993,36 @@ impl<'a> Serializer<'a> {
         Ok(())
     }
 
+    fn emit_array_inline(&mut self, first: &Cell<bool>) {
+        if first.get() {
+            self.dst.push_str("[")
+        } else {
+            self.dst.push_str(", ")
+        }
+    }
+
     fn array_type(&mut self, type_: ArrayState) -> Result<(), Error> {
         let prev = match self.state {
             State::Array { type_, .. } => type_,
             _ => return Ok(()),
         };
-        if prev.get().is_none() {
-            prev.set(Some(type_));
+        match prev.get() {
+            None => prev.set(Some(type_)),
+            Some(prev) if prev != type_ => {
+                return Err(Error::ArrayMixedType {
+                    key: key_path(&self.state),
+                    expected: prev.describe(),
+                    found: type_.describe(),
+                })
+            }
+            Some(_) => {}
         }
         Ok(())
     }
 
     fn escape_key(&mut self, key: &str) -> Result<(), Error> {
-        let ok = key.len() > 0
+        let ok = !self.settings.always_quote_keys
+            && key.len() > 0
             && key.chars().all(|c| match c {
                 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => true,
                 _ => false,
@@ -610,6 +1123,14 @@ impl<'a> Serializer<'a> {
                 }
                 (_, r) => r,
             }
+        } else if !is_key
+            && self.settings.prefer_literal_strings
+            && value.contains('\\')
+            && value
+                .chars()
+                .all(|c| c != '\'' && c != '\u{7f}' && (c == '\t' || c > '\u{1f}'))
+        {
+            Repr::Literal(value.to_string(), Type::OnelineSingle)
         } else {
             Repr::Std(Type::OnelineSingle)
         };
@@ -617,11 +1138,17 @@ impl<'a> Serializer<'a> {
             Repr::Literal(literal, ty) => {
                 // A pretty string
                 match ty {
-                    Type::NewlineTripple => self.dst.push_str("'''\n"),
+                    Type::NewlineTripple => {
+                        self.dst.push_str("'''");
+                        self.newline();
+                    }
                     Type::OnelineTripple => self.dst.push_str("'''"),
                     Type::OnelineSingle => self.dst.push('\''),
                 }
-                self.dst.push_str(&literal);
+                match self.settings.line_ending {
+                    LineEnding::Lf => self.dst.push_str(&literal),
+                    LineEnding::Crlf => self.dst.push_str(&literal.replace('\n', "\r\n")),
+                }
                 match ty {
                     Type::OnelineSingle => self.dst.push('\''),
                     _ => self.dst.push_str("'''"),
@@ -629,7 +1156,10 @@ impl<'a> Serializer<'a> {
             }
             Repr::Std(ty) => {
                 match ty {
-                    Type::NewlineTripple => self.dst.push_str("\"\"\"\n"),
+                    Type::NewlineTripple => {
+                        self.dst.push_str("\"\"\"");
+                        self.newline();
+                    }
                     // note: OnelineTripple can happen if do_pretty wants to do
                     // '''it's one line'''
                     // but settings.string.literal == false
@@ -640,7 +1170,7 @@ impl<'a> Serializer<'a> {
                         '\u{8}' => self.dst.push_str("\\b"),
                         '\u{9}' => self.dst.push_str("\\t"),
                         '\u{a}' => match ty {
-                            Type::NewlineTripple => self.dst.push('\n'),
+                            Type::NewlineTripple => self.newline(),
                             Type::OnelineSingle => self.dst.push_str("\\n"),
                             _ => unreachable!(),
                         },
@@ -663,13 +1193,70 @@ impl<'a> Serializer<'a> {
         Ok(())
     }
 
+    fn emit_empty_table(&mut self) -> Result<(), Error> {
+        if let State::Table { table_emitted, .. } = self.state {
+            if self.settings.empty_table_as_inline && !table_emitted.get() {
+                self.emit_key(ArrayState::StartedAsATable)?;
+                self.dst.push_str("{}");
+                self.newline();
+                return Ok(());
+            }
+        }
+        let state = self.state.clone();
+        self.emit_table_header(&state)
+    }
+
     fn emit_table_header(&mut self, state: &State<'_>) -> Result<(), Error> {
         let array_of_tables = match *state {
             State::End => return Ok(()),
+            State::Array {
+                parent: &State::End,
+                ..
+            } => return Err(Error::ArrayOfTablesAtRoot),
             State::Array { .. } => true,
             _ => false,
         };
 
+        self.emit_array_of_tables_ancestors(state)?;
+
+        if !self.settings.compact_tables {
+            match *state {
+                State::Table { first, .. } => {
+                    if !first.get() {
+                        // Newline if we are a table that is not the first
+                        // table in the document.
+                        self.newline();
+                    }
+                }
+                State::Array { parent, first, .. } => {
+                    if !first.get() {
+                        // Always newline if we are not the first item in the
+                        // table-array
+                        self.newline();
+                    } else if let State::Table { first, .. } = *parent {
+                        if !first.get() {
+                            // Newline if we are not the first item in the document
+                            self.newline();
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.dst.push_str("[");
+        if array_of_tables {
+            self.dst.push_str("[");
+        }
+        self.emit_key_part(state)?;
+        if array_of_tables {
+            self.dst.push_str("]");
+        }
+        self.dst.push(']');
+        self.newline();
+        Ok(())
+    }
+
+    fn emit_array_of_tables_ancestors(&mut self, state: &State<'_>) -> Result<(), Error> {
         // Unlike [..]s, we can't omit [[..]] ancestors, so be sure to emit table
         // headers for them.
         let mut p = state;
@@ -692,38 +1279,6 @@ impl<'a> Serializer<'a> {
                 break;
             }
         }
-
-        match *state {
-            State::Table { first, .. } => {
-                if !first.get() {
-                    // Newline if we are a table that is not the first
-                    // table in the document.
-                    self.dst.push('\n');
-                }
-            }
-            State::Array { parent, first, .. } => {
-                if !first.get() {
-                    // Always newline if we are not the first item in the
-                    // table-array
-                    self.dst.push('\n');
-                } else if let State::Table { first, .. } = *parent {
-                    if !first.get() {
-                        // Newline if we are not the first item in the document
-                        self.dst.push('\n');
-                    }
-                }
-            }
-            _ => {}
-        }
-        self.dst.push_str("[");
-        if array_of_tables {
-            self.dst.push_str("[");
-        }
-        self.emit_key_part(state)?;
-        if array_of_tables {
-            self.dst.push_str("]");
-        }
-        self.dst.push_str("]\n");
         Ok(())
     }
 
@@ -749,6 +1304,95 @@ impl<'a> Serializer<'a> {
     }
 }
 
+impl<'a> Serializer<'a> {
+    /// Serializes the entry at `key` of the table being serialized into its
+    /// own buffer, returning `None` if it is skipped.
+    ///
+    /// The entry is written as if another one preceded it in the table, as
+    /// the table header and [[..]] ancestors only precede the entry which ends
+    /// up first, see `emit_buffered`.
+    fn buffer_entry<T: ?Sized>(
+        &self,
+        key: &str,
+        value: &T,
+    ) -> Result<Option<(String, u8, String)>, Error>
+    where
+        T: ser::Serialize,
+    {
+        let first = Cell::new(false);
+        let table_emitted = Cell::new(false);
+        let mut dst = String::new();
+        let res = value.serialize(&mut Serializer {
+            dst: &mut dst,
+            state: State::Table {
+                key,
+                parent: &self.state,
+                first: &first,
+                table_emitted: &table_emitted,
+            },
+            settings: self.settings.clone(),
+        });
+        match res {
+            Ok(()) => {}
+            Err(Error::UnsupportedNone) => return Ok(None),
+            Err(e) => return Err(e.at_path(value, || field_path(&self.state, key))),
+        }
+        // Plain values come first, then arrays of tables and finally tables.
+        let rank = if !dst.trim_start().starts_with('[') {
+            0
+        } else if let Ok(Category::Array) = value.serialize(Categorize::<Error>::new()) {
+            1
+        } else {
+            2
+        };
+        Ok(Some((key.to_string(), rank, dst)))
+    }
+
+    /// Emits the entries collected by `buffer_entry`, ordered by rank and then
+    /// by the key order, if any.
+    fn emit_buffered(&mut self, mut buffer: Vec<(String, u8, String)>) -> Result<(), Error> {
+        if let Some(KeyOrder(ref cmp)) = self.settings.key_order {
+            buffer.sort_by(|a, b| cmp(&a.0, &b.0));
+        }
+        buffer.sort_by_key(|entry| entry.1);
+
+        let mut entries = buffer.iter();
+        let (rank, dst) = match entries.next() {
+            Some((_, rank, dst)) => (*rank, dst),
+            None => return self.emit_empty_table(),
+        };
+        let state = self.state.clone();
+        if rank == 0 {
+            // Like `_emit_key` for the first value of a table.
+            self.emit_table_header(&state)?;
+            self.dst.push_str(dst);
+        } else {
+            // Like `emit_table_header` for the first table of a table, which
+            // is not preceded by a newline.
+            let first = Cell::new(true);
+            let table_emitted = Cell::new(false);
+            self.emit_array_of_tables_ancestors(&State::Table {
+                key: "",
+                parent: &state,
+                first: &first,
+                table_emitted: &table_emitted,
+            })?;
+            let newline = self.settings.line_ending.as_str();
+            self.dst.push_str(dst.strip_prefix(newline).unwrap_or(dst));
+        }
+        for (_, _, dst) in entries {
+            self.dst.push_str(dst);
+        }
+        Ok(())
+    }
+}
+
+/// Rounds `v` to at most `digits` significant decimal digits.
+fn round_to_significant_digits(v: f64, digits: usize) -> f64 {
+    let s = format!("{:.*e}", digits.max(1) - 1, v);
+    s.parse().unwrap_or(v)
+}
+
 macro_rules! serialize_float {
     ($this:expr, $v:expr) => {{
         $this.emit_key(ArrayState::Started)?;
@@ -757,18 +1401,30 @@ macro_rules! serialize_float {
             (false, true, _) => write!($this.dst, "nan"),
             (true, false, true) => write!($this.dst, "-0.0"),
             (false, false, true) => write!($this.dst, "0.0"),
-            (_, false, false) => write!($this.dst, "{}", $v).and_then(|_| {
-                if $v % 1.0 == 0.0 {
-                    write!($this.dst, ".0")
-                } else {
-                    Ok(())
+            (_, false, false) => match $this.settings.float_precision {
+                Some(digits) => {
+                    let v = round_to_significant_digits(f64::from($v), digits);
+                    write!($this.dst, "{}", v).and_then(|_| {
+                        if v % 1.0 == 0.0 {
+                            write!($this.dst, ".0")
+                        } else {
+                            Ok(())
+                        }
+                    })
                 }
-            }),
+                None => write!($this.dst, "{}", $v).and_then(|_| {
+                    if $v % 1.0 == 0.0 {
+                        write!($this.dst, ".0")
+                    } else {
+                        Ok(())
+                    }
+                }),
+            },
         }
         .map_err(ser::Error::custom)?;
 
         if let State::Table { .. } = $this.state {
-            $this.dst.push_str("\n");
+            $this.newline();
         }
         return Ok(());
     }};
@@ -821,6 +1477,20 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
         self.display(v, ArrayState::Started)
     }
 
+    fn serialize_i128(self, v: i128) -> Result<(), Self::Error> {
+        if i64::try_from(v).is_err() {
+            return Err(ser::Error::custom("i128 value was out of range"));
+        }
+        self.display(v, ArrayState::Started)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Self::Error> {
+        if i64::try_from(v).is_err() {
+            return Err(ser::Error::custom("u128 value was out of range"));
+        }
+        self.display(v, ArrayState::Started)
+    }
+
     fn serialize_f32(self, v: f32) -> Result<(), Self::Error> {
         serialize_float!(self, v)
     }
@@ -838,7 +1508,7 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
         self.emit_key(ArrayState::Started)?;
         self.emit_str(value, false)?;
         if let State::Table { .. } = self.state {
-            self.dst.push_str("\n");
+            self.newline();
         }
         Ok(())
     }
@@ -863,8 +1533,16 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
         Err(Error::UnsupportedType)
     }
 
-    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Self::Error> {
-        Err(Error::UnsupportedType)
+    // Unit structs are typically used as markers, so they're emitted as an
+    // empty table, either as a header or inline with `empty_table_as_inline`.
+    // An empty document would be a table rather than a unit struct though, so
+    // they're still unsupported at the root.
+    fn serialize_unit_struct(self, name: &'static str) -> Result<(), Self::Error> {
+        use serde::ser::SerializeStruct;
+        if let State::End = self.state {
+            return Err(Error::UnsupportedType);
+        }
+        self.serialize_struct(name, 0)?.end()
     }
 
     fn serialize_unit_variant(
@@ -932,13 +1610,15 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
         self.serialize_seq(Some(len))
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
         self.array_type(ArrayState::StartedAsATable)?;
+        let buffered = len.is_none() || self.settings.key_order.is_some();
         Ok(SerializeTable::Table {
             ser: self,
             key: String::new(),
             first: Cell::new(true),
             table_emitted: Cell::new(false),
+            buffer: if buffered { Some(Vec::new()) } else { None },
         })
     }
 
@@ -952,11 +1632,13 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
             Ok(SerializeTable::Datetime(self))
         } else {
             self.array_type(ArrayState::StartedAsATable)?;
+            let buffered = self.settings.key_order.is_some();
             Ok(SerializeTable::Table {
                 ser: self,
                 key: String::new(),
                 first: Cell::new(true),
                 table_emitted: Cell::new(false),
+                buffer: if buffered { Some(Vec::new()) } else { None },
             })
         }
     }
@@ -980,7 +1662,7 @@ impl<'a, 'b> ser::SerializeSeq for SerializeSeq<'a, 'b> {
     where
         T: ser::Serialize,
     {
-        value.serialize(&mut Serializer {
+        let res = value.serialize(&mut Serializer {
             dst: &mut *self.ser.dst,
             state: State::Array {
                 parent: &self.ser.state,
@@ -989,8 +1671,13 @@ impl<'a, 'b> ser::SerializeSeq for SerializeSeq<'a, 'b> {
                 len: self.len,
             },
             settings: self.ser.settings.clone(),
-        })?;
-        self.first.set(false);
+        });
+        match res {
+            Ok(()) => self.first.set(false),
+            Err(Error::UnsupportedNone) if self.ser.settings.skip_none => {}
+            Err(Error::UnsupportedNone) => return Err(Error::NoneInArray),
+            Err(e) => return Err(e.at_path(value, || key_path(&self.ser.state))),
+        }
         Ok(())
     }
 
@@ -1001,11 +1688,13 @@ impl<'a, 'b> ser::SerializeSeq for SerializeSeq<'a, 'b> {
                 (Some(0..=1), _) | (_, &None) => {
                     self.ser.dst.push_str("]");
                 }
+                (Some(len), Some(a)) if len <= a.threshold => self.ser.dst.push(']'),
                 (_, &Some(ref a)) => {
                     if a.trailing_comma {
                         self.ser.dst.push_str(",");
                     }
-                    self.ser.dst.push_str("\n]");
+                    self.ser.newline();
+                    self.ser.dst.push(']');
                 }
             },
             None => {
@@ -1015,7 +1704,7 @@ impl<'a, 'b> ser::SerializeSeq for SerializeSeq<'a, 'b> {
             }
         }
         if let State::Table { .. } = self.ser.state {
-            self.ser.dst.push_str("\n");
+            self.ser.newline();
         }
         Ok(())
     }
@@ -1079,9 +1768,15 @@ impl<'a, 'b> ser::SerializeMap for SerializeTable<'a, 'b> {
     {
         match *self {
             SerializeTable::Datetime(_) => panic!(), // shouldn't be possible
-            SerializeTable::Table { ref mut key, .. } => {
+            SerializeTable::Table {
+                ref ser,
+                ref mut key,
+                ..
+            } => {
                 key.truncate(0);
-                *key = input.serialize(StringExtractor)?;
+                *key = input.serialize(StringExtractor {
+                    int_keys: ser.settings.stringify_int_keys,
+                })?;
             }
         }
         Ok(())
@@ -1098,7 +1793,7 @@ impl<'a, 'b> ser::SerializeMap for SerializeTable<'a, 'b> {
                 ref key,
                 ref first,
                 ref table_emitted,
-                ..
+                buffer: None,
             } => {
                 let res = value.serialize(&mut Serializer {
                     dst: &mut *ser.dst,
@@ -1113,7 +1808,17 @@ impl<'a, 'b> ser::SerializeMap for SerializeTable<'a, 'b> {
                 match res {
                     Ok(()) => first.set(false),
                     Err(Error::UnsupportedNone) => {}
-                    Err(e) => return Err(e),
+                    Err(e) => return Err(e.at_path(value, || field_path(&ser.state, key))),
+                }
+            }
+            SerializeTable::Table {
+                ref ser,
+                ref key,
+                buffer: Some(ref mut buffer),
+                ..
+            } => {
+                if let Some(entry) = ser.buffer_entry(key, value)? {
+                    buffer.push(entry);
                 }
             }
         }
@@ -1123,12 +1828,21 @@ impl<'a, 'b> ser::SerializeMap for SerializeTable<'a, 'b> {
     fn end(self) -> Result<(), Error> {
         match self {
             SerializeTable::Datetime(_) => panic!(), // shouldn't be possible
-            SerializeTable::Table { ser, first, .. } => {
+            SerializeTable::Table {
+                ser,
+                first,
+                buffer: None,
+                ..
+            } => {
                 if first.get() {
-                    let state = ser.state.clone();
-                    ser.emit_table_header(&state)?;
+                    ser.emit_empty_table()?;
                 }
             }
+            SerializeTable::Table {
+                ser,
+                buffer: Some(buffer),
+                ..
+            } => ser.emit_buffered(buffer)?,
         }
         Ok(())
     }
@@ -1154,6 +1868,7 @@ impl<'a, 'b> ser::SerializeStruct for SerializeTable<'a, 'b> {
                 ref mut ser,
                 ref first,
                 ref table_emitted,
+                buffer: None,
                 ..
             } => {
                 let res = value.serialize(&mut Serializer {
@@ -1169,7 +1884,16 @@ impl<'a, 'b> ser::SerializeStruct for SerializeTable<'a, 'b> {
                 match res {
                     Ok(()) => first.set(false),
                     Err(Error::UnsupportedNone) => {}
-                    Err(e) => return Err(e),
+                    Err(e) => return Err(e.at_path(value, || field_path(&ser.state, key))),
+                }
+            }
+            SerializeTable::Table {
+                ref ser,
+                buffer: Some(ref mut buffer),
+                ..
+            } => {
+                if let Some(entry) = ser.buffer_entry(key, value)? {
+                    buffer.push(entry);
                 }
             }
         }
@@ -1179,12 +1903,17 @@ impl<'a, 'b> ser::SerializeStruct for SerializeTable<'a, 'b> {
     fn end(self) -> Result<(), Error> {
         match self {
             SerializeTable::Datetime(_) => {}
-            SerializeTable::Table { ser, first, .. } => {
+            SerializeTable::Table {
+                ser,
+                first,
+                buffer: None,
+                ..
+            } => {
                 if first.get() {
-                    let state = ser.state.clone();
-                    ser.emit_table_header(&state)?;
+                    ser.emit_empty_table()?;
                 }
             }
+            table @ SerializeTable::Table { .. } => ser::SerializeMap::end(table)?,
         }
         Ok(())
     }
@@ -1361,7 +2090,20 @@ impl<'a, 'b> ser::Serializer for DateStrEmitter<'a, 'b> {
     }
 }
 
-struct StringExtractor;
+struct StringExtractor {
+    /// Whether integer keys are accepted, see `Serializer::stringify_int_keys`
+    int_keys: bool,
+}
+
+impl StringExtractor {
+    fn int_key<T: fmt::Display>(self, v: T) -> Result<String, Error> {
+        if self.int_keys {
+            Ok(v.to_string())
+        } else {
+            Err(Error::KeyNotString)
+        }
+    }
+}
 
 impl ser::Serializer for StringExtractor {
     type Ok = String;
@@ -1378,36 +2120,36 @@ impl ser::Serializer for StringExtractor {
         Err(Error::KeyNotString)
     }
 
-    fn serialize_i8(self, _v: i8) -> Result<String, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_i8(self, v: i8) -> Result<String, Self::Error> {
+        self.int_key(v)
     }
 
-    fn serialize_i16(self, _v: i16) -> Result<String, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_i16(self, v: i16) -> Result<String, Self::Error> {
+        self.int_key(v)
     }
 
-    fn serialize_i32(self, _v: i32) -> Result<String, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_i32(self, v: i32) -> Result<String, Self::Error> {
+        self.int_key(v)
     }
 
-    fn serialize_i64(self, _v: i64) -> Result<String, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_i64(self, v: i64) -> Result<String, Self::Error> {
+        self.int_key(v)
     }
 
-    fn serialize_u8(self, _v: u8) -> Result<String, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_u8(self, v: u8) -> Result<String, Self::Error> {
+        self.int_key(v)
     }
 
-    fn serialize_u16(self, _v: u16) -> Result<String, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_u16(self, v: u16) -> Result<String, Self::Error> {
+        self.int_key(v)
     }
 
-    fn serialize_u32(self, _v: u32) -> Result<String, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_u32(self, v: u32) -> Result<String, Self::Error> {
+        self.int_key(v)
     }
 
-    fn serialize_u64(self, _v: u64) -> Result<String, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_u64(self, v: u64) -> Result<String, Self::Error> {
+        self.int_key(v)
     }
 
     fn serialize_f32(self, _v: f32) -> Result<String, Self::Error> {
@@ -1531,18 +2273,58 @@ impl ser::Serializer for StringExtractor {
     }
 }
 
+/// Returns the dotted key path leading to `state`, for use in error messages.
+fn key_path(state: &State<'_>) -> String {
+    match *state {
+        State::End => String::new(),
+        State::Array { parent, .. } => key_path(parent),
+        State::Table { key, parent, .. } => field_path(parent, key),
+    }
+}
+
+/// Returns the dotted key path of the value at `key` in the table at
+/// `parent`.
+fn field_path(parent: &State<'_>, key: &str) -> String {
+    let mut path = key_path(parent);
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(&crate::value::path_segment(key));
+    path
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Error::UnsupportedType => "unsupported Rust type".fmt(f),
             Error::KeyNotString => "map key was not a string".fmt(f),
-            Error::ValueAfterTable => "values must be emitted before tables".fmt(f),
+            Error::ArrayMixedType {
+                ref key,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "arrays cannot mix tables and other values, expected a {} but found a {}",
+                    expected, found
+                )?;
+                if !key.is_empty() {
+                    write!(f, " (at `{}`)", key)?;
+                }
+                Ok(())
+            }
+            Error::ValueAfterTable { ref key } => {
+                write!(f, "values must be emitted before tables (at `{}`)", key)
+            }
+            Error::ArrayOfTablesAtRoot => {
+                "TOML documents must have a table at the root, not an array of tables".fmt(f)
+            }
             Error::DateInvalid => "a serialized date was invalid".fmt(f),
             Error::NumberInvalid => "a serialized number was invalid".fmt(f),
             Error::UnsupportedNone => "unsupported None value".fmt(f),
+            Error::NoneInArray => "arrays cannot contain None values".fmt(f),
             Error::Custom(ref s) => s.fmt(f),
             Error::KeyNewline => unreachable!(),
-            Error::ArrayMixedType => unreachable!(),
             Error::__Nonexhaustive => panic!(),
         }
     }
@@ -1550,12 +2332,50 @@ impl fmt::Display for Error {
 
 impl error::Error for Error {}
 
+impl std::convert::From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+    }
+}
+
 impl ser::Error for Error {
     fn custom<T: fmt::Display>(msg: T) -> Error {
         Error::Custom(msg.to_string())
     }
 }
 
+impl Error {
+    /// Returns the dotted key path of the value which caused this error, if
+    /// it is known.
+    pub fn key(&self) -> Option<&str> {
+        match *self {
+            Error::ValueAfterTable { ref key } => Some(key),
+            Error::ArrayMixedType { ref key, .. } if !key.is_empty() => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Records the dotted key path of `value` in a custom error raised by its
+    /// own `Serialize` impl. The entries of tables and the elements of arrays
+    /// record their own path, so errors passing through them are left as is.
+    fn at_path<T: ?Sized>(self, value: &T, path: impl FnOnce() -> String) -> Error
+    where
+        T: ser::Serialize,
+    {
+        let message = match self {
+            Error::Custom(message) => message,
+            e => return e,
+        };
+        match value.serialize(Categorize::<Error>::new()) {
+            Ok(Category::Array) | Ok(Category::Table) => Error::Custom(message),
+            _ => match path() {
+                ref path if path.is_empty() => Error::Custom(message),
+                path => Error::Custom(format!("{} (at `{}`)", message, path)),
+            },
+        }
+    }
+}
+
 enum Category {
     Primitive,
     Array,
@@ -1665,6 +2485,14 @@ impl<E: ser::Error> ser::Serializer for Categorize<E> {
         Ok(Category::Primitive)
     }
 
+    fn serialize_i128(self, _: i128) -> Result<Self::Ok, Self::Error> {
+        Ok(Category::Primitive)
+    }
+
+    fn serialize_u128(self, _: u128) -> Result<Self::Ok, Self::Error> {
+        Ok(Category::Primitive)
+    }
+
     fn serialize_f32(self, _: f32) -> Result<Self::Ok, Self::Error> {
         Ok(Category::Primitive)
     }
@@ -1698,7 +2526,7 @@ impl<E: ser::Error> ser::Serializer for Categorize<E> {
     }
 
     fn serialize_unit_struct(self, _: &'static str) -> Result<Self::Ok, Self::Error> {
-        Err(ser::Error::custom("unsupported"))
+        Ok(Category::Table)
     }
 
     fn serialize_unit_variant(
@@ -3,6 +3,7 @@ use std::borrow::Borrow;
 use std::cmp::Ordering;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::ops::Range;
 
 pub(crate) const NAME: &str = "$__toml_private_Spanned";
 pub(crate) const START: &str = "$__toml_private_start";
@@ -27,6 +28,7 @@ pub(crate) const VALUE: &str = "$__toml_private_value";
 ///
 ///     assert_eq!(u.s.start(), 4);
 ///     assert_eq!(u.s.end(), 11);
+///     assert_eq!(&t[u.s.range()], "\"value\"");
 ///     assert_eq!(u.s.get_ref(), "value");
 ///     assert_eq!(u.s.into_inner(), String::from("value"));
 /// }
@@ -57,6 +59,12 @@ impl<T> Spanned<T> {
         (self.start, self.end)
     }
 
+    /// Get the span of the contained value as a range of byte offsets, which
+    /// can be used to slice the source or be passed to diagnostics libraries.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
     /// Consumes the spanned value and returns the contained value.
     pub fn into_inner(self) -> T {
         self.value
@@ -0,0 +1,142 @@
+//! Definition of a TOML value annotated with source spans
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de;
+
+use crate::datetime::{Datetime, DatetimeFromString};
+use crate::value::DatetimeOrTable;
+use crate::Spanned;
+
+/// A TOML value together with the byte range at which it is defined in the
+/// source.
+///
+/// Note that tables defined with a `[header]`, including the document's root
+/// table, have no span of their own. Their span is reported as `0..0`, except
+/// for the root table returned from `from_str_spanned` which spans the whole
+/// input.
+pub type SpannedValue = Spanned<ValueKind>;
+
+/// Type representing a table of spanned values, payload of the
+/// `ValueKind::Table` variant
+pub type SpannedTable = BTreeMap<String, SpannedValue>;
+
+/// Representation of a TOML value whose nested values are all spanned.
+#[derive(PartialEq, Clone, Debug)]
+pub enum ValueKind {
+    /// Represents a TOML string
+    String(String),
+    /// Represents a TOML integer
+    Integer(i64),
+    /// Represents a TOML float
+    Float(f64),
+    /// Represents a TOML boolean
+    Boolean(bool),
+    /// Represents a TOML datetime
+    Datetime(Datetime),
+    /// Represents a TOML array
+    Array(Vec<SpannedValue>),
+    /// Represents a TOML table
+    Table(SpannedTable),
+}
+
+impl ValueKind {
+    /// Returns a human-readable representation of the type of this value.
+    pub fn type_str(&self) -> &'static str {
+        match *self {
+            ValueKind::String(..) => "string",
+            ValueKind::Integer(..) => "integer",
+            ValueKind::Float(..) => "float",
+            ValueKind::Boolean(..) => "boolean",
+            ValueKind::Datetime(..) => "datetime",
+            ValueKind::Array(..) => "array",
+            ValueKind::Table(..) => "table",
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for ValueKind {
+    fn deserialize<D>(deserializer: D) -> Result<ValueKind, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct ValueKindVisitor;
+
+        impl<'de> de::Visitor<'de> for ValueKindVisitor {
+            type Value = ValueKind;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("any valid TOML value")
+            }
+
+            fn visit_bool<E>(self, value: bool) -> Result<ValueKind, E> {
+                Ok(ValueKind::Boolean(value))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<ValueKind, E> {
+                Ok(ValueKind::Integer(value))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<ValueKind, E> {
+                if value <= i64::MAX as u64 {
+                    Ok(ValueKind::Integer(value as i64))
+                } else {
+                    Err(de::Error::custom("u64 value was too large"))
+                }
+            }
+
+            fn visit_f64<E>(self, value: f64) -> Result<ValueKind, E> {
+                Ok(ValueKind::Float(value))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<ValueKind, E> {
+                Ok(ValueKind::String(value.into()))
+            }
+
+            fn visit_string<E>(self, value: String) -> Result<ValueKind, E> {
+                Ok(ValueKind::String(value))
+            }
+
+            fn visit_seq<V>(self, mut visitor: V) -> Result<ValueKind, V::Error>
+            where
+                V: de::SeqAccess<'de>,
+            {
+                let mut vec = Vec::new();
+                while let Some(elem) = visitor.next_element()? {
+                    vec.push(elem);
+                }
+                Ok(ValueKind::Array(vec))
+            }
+
+            fn visit_map<V>(self, mut visitor: V) -> Result<ValueKind, V::Error>
+            where
+                V: de::MapAccess<'de>,
+            {
+                let mut key = String::new();
+                let datetime = visitor.next_key_seed(DatetimeOrTable { key: &mut key })?;
+                match datetime {
+                    Some(true) => {
+                        let date: DatetimeFromString = visitor.next_value()?;
+                        return Ok(ValueKind::Datetime(date.value));
+                    }
+                    None => return Ok(ValueKind::Table(BTreeMap::new())),
+                    Some(false) => {}
+                }
+                let mut map = BTreeMap::new();
+                map.insert(key, visitor.next_value()?);
+                while let Some(key) = visitor.next_key::<String>()? {
+                    if map.contains_key(&key) {
+                        let msg = format!("duplicate key: `{}`", key);
+                        return Err(de::Error::custom(msg));
+                    }
+                    let value = visitor.next_value()?;
+                    map.insert(key, value);
+                }
+                Ok(ValueKind::Table(map))
+            }
+        }
+
+        deserializer.deserialize_any(ValueKindVisitor)
+    }
+}
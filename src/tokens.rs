@@ -67,6 +67,7 @@ pub enum Error {
 pub struct Tokenizer<'a> {
     input: &'a str,
     chars: CrlfFold<'a>,
+    hex_escapes: bool,
 }
 
 #[derive(Clone)]
@@ -87,12 +88,18 @@ impl<'a> Tokenizer<'a> {
             chars: CrlfFold {
                 chars: input.char_indices(),
             },
+            hex_escapes: false,
         };
         // Eat utf-8 BOM
         t.eatc('\u{feff}');
         t
     }
 
+    /// Accept the non-standard `\xNN` escape in basic strings.
+    pub fn set_hex_escapes(&mut self, allow: bool) {
+        self.hex_escapes = allow;
+    }
+
     pub fn next(&mut self) -> Result<Option<(Span, Token<'a>)>, Error> {
         let (start, token) = match self.one() {
             Some((start, '\n')) => (start, Newline),
@@ -247,6 +254,31 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    /// Skips to the end of the key/value pair or table header starting at the
+    /// current position, including the following newline.
+    ///
+    /// Unlike `skip_to_newline` this doesn't stop at a newline within an array
+    /// or inline table, so that the rest of it isn't read as further lines.
+    pub fn skip_statement(&mut self) {
+        drop(self.eat_whitespace());
+        if let Ok(Some((_, LeftBracket))) = self.peek() {
+            // Table headers can't span lines
+            return self.skip_to_newline();
+        }
+        let mut depth = 0usize;
+        loop {
+            match self.next() {
+                Ok(Some((_, LeftBracket))) | Ok(Some((_, LeftBrace))) => depth += 1,
+                Ok(Some((_, RightBracket))) | Ok(Some((_, RightBrace))) => {
+                    depth = depth.saturating_sub(1)
+                }
+                Ok(Some((_, Newline))) | Err(Error::NewlineInString(_)) if depth == 0 => break,
+                Ok(Some(_)) | Err(_) => {}
+                Ok(None) => break,
+            }
+        }
+    }
+
     fn eatc(&mut self, ch: char) -> bool {
         match self.chars.clone().next() {
             Some((_, ch2)) if ch == ch2 => {
@@ -269,6 +301,20 @@ impl<'a> Tokenizer<'a> {
         self.input
     }
 
+    /// Repositions the tokenizer so the next token is read starting at the
+    /// byte `offset` into the input.
+    ///
+    /// Panics if `offset` does not lie on a char boundary.
+    pub fn seek(&mut self, offset: usize) {
+        assert!(self.input.is_char_boundary(offset));
+        let mut chars = self.input.char_indices();
+        let skip = self.input[..offset].chars().count();
+        if skip > 0 {
+            chars.nth(skip - 1);
+        }
+        self.chars = CrlfFold { chars };
+    }
+
     fn whitespace_token(&mut self, start: usize) -> Token<'a> {
         while self.eatc(' ') || self.eatc('\t') {
             // ...
@@ -341,6 +387,10 @@ impl<'a> Tokenizer<'a> {
                             val.push(delim);
                             continue 'outer;
                         }
+                        // As quotes right after the opening delimiter, up to
+                        // two quotes before the closing delimiter are part of
+                        // the string, so `""""a""""` is `"a"`. Any further
+                        // quote starts a new token.
                         if self.eatc(delim) {
                             val.push(delim);
                             i += 1;
@@ -389,6 +439,7 @@ impl<'a> Tokenizer<'a> {
                         let len = if c == 'u' { 4 } else { 8 };
                         val.push(me.hex(start, i, len)?);
                     }
+                    Some((i, 'x')) if me.hex_escapes => val.push(me.hex(start, i, 2)?),
                     Some((i, c @ ' ')) | Some((i, c @ '\t')) | Some((i, c @ '\n')) if multi => {
                         if c != '\n' {
                             while let Some((_, ch)) = me.chars.clone().next() {
@@ -737,4 +788,17 @@ mod tests {
         assert_eq!(t.next(), Err(Error::Unexpected(1, '\u{0}')));
         assert!(t.next().unwrap().is_none());
     }
+
+    #[test]
+    fn seek() {
+        let mut t = Tokenizer::new("a = 'é'\nb = 1");
+        t.seek(4);
+        assert_eq!(t.current(), 4);
+        let (span, _) = t.next().unwrap().unwrap();
+        assert_eq!((span.start, span.end), (4, 8));
+        t.seek(9);
+        assert_eq!(t.next().unwrap().unwrap().1, Token::Keylike("b"));
+        t.seek(0);
+        assert_eq!(t.next().unwrap().unwrap().1, Token::Keylike("a"));
+    }
 }
@@ -0,0 +1,172 @@
+//! Validation of TOML values against a minimal schema
+//!
+//! A [`Schema`] describes the expected type of a value and, for tables, the
+//! keys it must or may contain along with their own schemas. It isn't meant
+//! to be a complete schema language, only a quick way to check the shape of
+//! a configuration file and report every mismatch at once.
+//!
+//! ```
+//! use toml::validate::Schema;
+//! use toml::Value;
+//!
+//! let schema = Schema::table()
+//!     .required("name", Schema::of_type("string"))
+//!     .optional("server", Schema::table().required("port", Schema::of_type("integer")));
+//!
+//! let config: Value = "name = 'app'\n[server]\nport = 'http'".parse().unwrap();
+//! let errors = config.validate(&schema).unwrap_err();
+//! assert_eq!(errors[0].to_string(), "expected type `integer` at `server.port`, found `string`");
+//! ```
+
+use std::error;
+use std::fmt;
+
+use crate::value::{path_segment, Value};
+
+/// The expected shape of a value, see `Value::validate`.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Schema {
+    type_: Option<&'static str>,
+    fields: Vec<Field>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct Field {
+    key: String,
+    required: bool,
+    schema: Schema,
+}
+
+impl Schema {
+    /// Creates a schema which accepts any value.
+    pub fn any() -> Schema {
+        Schema::default()
+    }
+
+    /// Creates a schema which accepts values of the given type, as named by
+    /// `Value::type_str` (e.g. `"string"` or `"array"`).
+    pub fn of_type(type_: &'static str) -> Schema {
+        Schema {
+            type_: Some(type_),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Creates a schema which accepts tables. Their keys can then be
+    /// described with `required` and `optional`.
+    pub fn table() -> Schema {
+        Schema::of_type("table")
+    }
+
+    /// Adds a key which the table must contain, with the schema its value
+    /// must satisfy.
+    pub fn required(self, key: &str, schema: Schema) -> Schema {
+        self.field(key, true, schema)
+    }
+
+    /// Adds a key which the table may contain, with the schema its value must
+    /// satisfy when present.
+    pub fn optional(self, key: &str, schema: Schema) -> Schema {
+        self.field(key, false, schema)
+    }
+
+    fn field(mut self, key: &str, required: bool, schema: Schema) -> Schema {
+        self.fields.push(Field {
+            key: key.to_string(),
+            required,
+            schema,
+        });
+        self
+    }
+}
+
+/// A mismatch between a value and a schema, as returned by `Value::validate`.
+///
+/// Each error carries the dotted path of the offending value, in the same
+/// format as `Value::diff`. The root value has an empty path.
+#[derive(PartialEq, Clone, Debug)]
+pub enum ValidationError {
+    /// A required key is missing; the path is the one the key would have.
+    MissingKey(String),
+    /// A value doesn't have the type expected by the schema.
+    WrongType {
+        /// Path of the value.
+        path: String,
+        /// Type required by the schema.
+        expected: &'static str,
+        /// Actual type of the value.
+        found: &'static str,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ValidationError::MissingKey(ref path) => write!(f, "missing key `{}`", path),
+            ValidationError::WrongType {
+                ref path,
+                expected,
+                found,
+            } => {
+                write!(f, "expected type `{}`", expected)?;
+                if !path.is_empty() {
+                    write!(f, " at `{}`", path)?;
+                }
+                write!(f, ", found `{}`", found)
+            }
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+impl Value {
+    /// Checks this value against a schema.
+    ///
+    /// Unlike deserialization, which stops at the first problem, this
+    /// reports every missing key and mismatched type. Keys which the schema
+    /// doesn't mention are ignored. Returns `Ok(())` if there were no
+    /// mismatches.
+    pub fn validate(&self, schema: &Schema) -> Result<(), Vec<ValidationError>> {
+        fn validate(
+            value: &Value,
+            schema: &Schema,
+            path: &mut Vec<String>,
+            errors: &mut Vec<ValidationError>,
+        ) {
+            if let Some(expected) = schema.type_ {
+                if value.type_str() != expected {
+                    errors.push(ValidationError::WrongType {
+                        path: path.join("."),
+                        expected,
+                        found: value.type_str(),
+                    });
+                    return;
+                }
+            }
+            let table = match *value {
+                Value::Table(ref table) => table,
+                _ => return,
+            };
+            for field in &schema.fields {
+                path.push(path_segment(&field.key));
+                match table.get(&field.key) {
+                    Some(value) => validate(value, &field.schema, path, errors),
+                    None if field.required => {
+                        errors.push(ValidationError::MissingKey(path.join(".")))
+                    }
+                    None => {}
+                }
+                path.pop();
+            }
+        }
+
+        let mut errors = Vec::new();
+        validate(self, schema, &mut Vec::new(), &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
@@ -1,8 +1,11 @@
 //! Definition of a TOML value
 
 use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::error;
 use std::fmt;
 use std::hash::Hash;
+use std::iter::FromIterator;
 use std::mem::discriminant;
 use std::ops;
 use std::str::FromStr;
@@ -36,6 +39,63 @@ pub enum Value {
     Table(Table),
 }
 
+/// Formatting options for `Value::to_string_pretty`.
+#[derive(Debug, Clone)]
+pub struct PrettyOptions {
+    /// Whether to separate tables with a blank line. Defaults to `true`.
+    pub blank_line_between_tables: bool,
+    /// Arrays with more elements than this are written with one element per
+    /// line, `None` keeps every array on a single line. Defaults to `Some(1)`.
+    pub array_wrap_threshold: Option<usize>,
+    /// Indentation of the elements of wrapped arrays. Defaults to 4.
+    pub indent: usize,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> PrettyOptions {
+        PrettyOptions {
+            blank_line_between_tables: true,
+            array_wrap_threshold: Some(1),
+            indent: 4,
+        }
+    }
+}
+
+/// A single difference between two values, as returned by `Value::diff`.
+///
+/// Each change carries the dotted path of the value it applies to, with
+/// array elements addressed by their index (e.g. `servers.0.name`) and keys
+/// which are not bare keys quoted. The root value has an empty path.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Change {
+    /// A value present only in the new value.
+    Added(String, Value),
+    /// A value present only in the old value.
+    Removed(String, Value),
+    /// A value present in both, with the old and the new contents.
+    Modified(String, Value, Value),
+}
+
+/// An error returned by `Value::rename_key`.
+#[derive(PartialEq, Clone, Debug)]
+pub enum RenameError {
+    /// There is no key at the given path.
+    MissingKey(String),
+    /// The table already has a key with the new name; this is its path.
+    KeyExists(String),
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RenameError::MissingKey(ref path) => write!(f, "no key at `{}`", path),
+            RenameError::KeyExists(ref path) => write!(f, "key `{}` already exists", path),
+        }
+    }
+}
+
+impl error::Error for RenameError {}
+
 /// Type representing a TOML array, payload of the `Value::Array` variant
 pub type Array = Vec<Value>;
 
@@ -97,6 +157,194 @@ impl Value {
         index.index_mut(self)
     }
 
+    /// Returns whether this is a table containing the given key.
+    ///
+    /// Unlike `contains_path`, the key is used as-is, so `a.b` is a single
+    /// key containing a period.
+    pub fn contains_key(&self, key: &str) -> bool {
+        match *self {
+            Value::Table(ref table) => table.contains_key(key),
+            _ => false,
+        }
+    }
+
+    /// Returns whether a dotted path such as `a.b.0` resolves to a value,
+    /// see `lookup`.
+    pub fn contains_path(&self, path: &str) -> bool {
+        self.lookup(path).is_some()
+    }
+
+    /// Looks up a value by a dotted path such as `a.b.0`.
+    ///
+    /// The path is split with `toml::parse_path`, so segments may be quoted
+    /// keys. Each segment names a key in a table, or an index if the value
+    /// at that point is an array. Returns `None` if the path is invalid or
+    /// doesn't resolve to a value.
+    ///
+    /// A path with an empty segment, such as `a.`, `.a` or `a..b`, is
+    /// invalid rather than naming a key with an empty name. Such a key can
+    /// still be looked up by quoting it, as in `a."".b`.
+    pub fn lookup(&self, path: &str) -> Option<&Value> {
+        let mut cur = self;
+        for segment in crate::de::parse_path(path)? {
+            cur = match *cur {
+                Value::Array(ref array) => array.get(segment.parse::<usize>().ok()?)?,
+                _ => cur.get(&segment)?,
+            };
+        }
+        Some(cur)
+    }
+
+    /// Looks up a value by a dotted path like `Value::lookup`, but compares
+    /// table keys ignoring ASCII case.
+    ///
+    /// If several keys of a table match a segment, the first one in the
+    /// table's iteration order is used: the sorted order by default, or the
+    /// insertion order with the `preserve_order` feature.
+    pub fn lookup_ci(&self, path: &str) -> Option<&Value> {
+        let mut cur = self;
+        for segment in crate::de::parse_path(path)? {
+            cur = match *cur {
+                Value::Array(ref array) => array.get(segment.parse::<usize>().ok()?)?,
+                Value::Table(ref table) => {
+                    table
+                        .iter()
+                        .find(|(key, _)| key.eq_ignore_ascii_case(&segment))?
+                        .1
+                }
+                _ => return None,
+            };
+        }
+        Some(cur)
+    }
+
+    /// Mutably looks up a value by a dotted path such as `a.b.0`.
+    ///
+    /// See `Value::lookup` for the path syntax.
+    pub fn lookup_mut(&mut self, path: &str) -> Option<&mut Value> {
+        let mut cur = self;
+        for segment in crate::de::parse_path(path)? {
+            cur = match *cur {
+                Value::Array(ref mut array) => array.get_mut(segment.parse::<usize>().ok()?)?,
+                _ => cur.get_mut(&segment)?,
+            };
+        }
+        Some(cur)
+    }
+
+    /// Gets a mutable reference to the value at `key` in a TOML table,
+    /// inserting an empty table first if the key is not present.
+    ///
+    /// This makes it possible to build nested tables by chaining calls, for
+    /// example `root.entry("a").entry("b")` for `[a.b]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is not a table.
+    pub fn entry(&mut self, key: &str) -> &mut Value {
+        match *self {
+            Value::Table(ref mut table) => table
+                .entry(key)
+                .or_insert_with(|| Value::Table(Table::new())),
+            ref other => panic!("cannot call entry on a {}", other.type_str()),
+        }
+    }
+
+    /// Gets a mutable reference to the value at a dotted path such as `a.b.c`,
+    /// inserting the value returned by `f` if the last key is not present.
+    ///
+    /// Missing intermediate tables are created empty, like with repeated
+    /// calls to `Value::entry`. See `Value::lookup` for the path syntax, but
+    /// note that array indices aren't supported here: every segment names a
+    /// key of a table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` isn't a valid dotted key, or if `self` or any value
+    /// along the path other than the last one is not a table.
+    pub fn get_or_insert_with<F>(&mut self, path: &str, f: F) -> &mut Value
+    where
+        F: FnOnce() -> Value,
+    {
+        let segments =
+            crate::de::parse_path(path).unwrap_or_else(|| panic!("invalid key path: `{}`", path));
+        let (last, parents) = segments.split_last().unwrap();
+        let mut cur = self;
+        for segment in parents {
+            cur = cur
+                .path_table(path)
+                .entry(segment.as_str())
+                .or_insert_with(|| Value::Table(Table::new()));
+        }
+        cur.path_table(path).entry(last.as_str()).or_insert_with(f)
+    }
+
+    fn path_table(&mut self, path: &str) -> &mut Table {
+        match *self {
+            Value::Table(ref mut table) => table,
+            ref other => panic!("cannot insert at `{}` into a {}", path, other.type_str()),
+        }
+    }
+
+    /// Renames the key at a dotted path such as `a.b.c`, keeping its value.
+    ///
+    /// The parent of the key is found like with `Value::lookup_mut`, so array
+    /// indices may be used for the segments before the last one. With the
+    /// `preserve_order` feature the key keeps its position in the table;
+    /// otherwise it moves to its sorted position as usual.
+    ///
+    /// Fails with `RenameError::MissingKey` if there is no key at `path`, and
+    /// with `RenameError::KeyExists` if the table already has a key named
+    /// `new_name`. Renaming a key to its own name does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut value: toml::Value = "[server]\nhost = 'x'".parse().unwrap();
+    /// value.rename_key("server.host", "address").unwrap();
+    /// assert_eq!(value["server"]["address"].as_str(), Some("x"));
+    /// ```
+    pub fn rename_key(&mut self, path: &str, new_name: &str) -> Result<(), RenameError> {
+        let missing = || RenameError::MissingKey(path.to_string());
+        let segments = crate::de::parse_path(path).ok_or_else(missing)?;
+        let (last, parents) = segments.split_last().unwrap();
+        let mut cur = self;
+        for segment in parents {
+            cur = match *cur {
+                Value::Array(ref mut array) => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(move |i| array.get_mut(i)),
+                _ => cur.get_mut(segment),
+            }
+            .ok_or_else(missing)?;
+        }
+        let table = match *cur {
+            Value::Table(ref mut table) if table.contains_key(last) => table,
+            _ => return Err(missing()),
+        };
+        if last == new_name {
+            return Ok(());
+        }
+        if table.contains_key(new_name) {
+            let mut target = parents.iter().map(|s| path_segment(s)).collect::<Vec<_>>();
+            target.push(path_segment(new_name));
+            return Err(RenameError::KeyExists(target.join(".")));
+        }
+        let old = std::mem::take(table);
+        *table = old
+            .into_iter()
+            .map(|(key, value)| {
+                if key == *last {
+                    (new_name.to_string(), value)
+                } else {
+                    (key, value)
+                }
+            })
+            .collect();
+        Ok(())
+    }
+
     /// Extracts the integer value if it is an integer.
     pub fn as_integer(&self) -> Option<i64> {
         match *self {
@@ -123,6 +371,18 @@ impl Value {
         self.as_float().is_some()
     }
 
+    /// Extracts the numeric value as a float if it is a float or an integer.
+    ///
+    /// Integers are converted with `as`, so those with a magnitude above
+    /// 2<sup>53</sup> may lose precision.
+    pub fn as_f64_lossy(&self) -> Option<f64> {
+        match *self {
+            Value::Float(f) => Some(f),
+            Value::Integer(i) => Some(i as f64),
+            _ => None,
+        }
+    }
+
     /// Extracts the boolean value if it is a boolean.
     pub fn as_bool(&self) -> Option<bool> {
         match *self {
@@ -185,11 +445,61 @@ impl Value {
         }
     }
 
+    /// Appends a value to the end of this array.
+    ///
+    /// If this isn't an array, the value is handed back as `Err` and `self`
+    /// is left unchanged.
+    pub fn array_push(&mut self, value: Value) -> Result<(), Value> {
+        match *self {
+            Value::Array(ref mut a) => {
+                a.push(value);
+                Ok(())
+            }
+            _ => Err(value),
+        }
+    }
+
+    /// Appends all the values of an iterator to the end of this array.
+    ///
+    /// If this isn't an array, the iterator is handed back as `Err` without
+    /// consuming any of its values.
+    pub fn array_extend<I>(&mut self, iter: I) -> Result<(), I>
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        match *self {
+            Value::Array(ref mut a) => {
+                a.extend(iter);
+                Ok(())
+            }
+            _ => Err(iter),
+        }
+    }
+
     /// Tests whether this value is an array.
     pub fn is_array(&self) -> bool {
         self.as_array().is_some()
     }
 
+    /// Consumes this value, returning the array if it is an array.
+    ///
+    /// Otherwise the value is handed back as `Err`.
+    pub fn into_array(self) -> Result<Array, Value> {
+        match self {
+            Value::Array(a) => Ok(a),
+            other => Err(other),
+        }
+    }
+
+    /// Extracts the tables of an array of tables, such as one defined with
+    /// `[[header]]`s.
+    ///
+    /// Returns `None` unless this is an array whose elements are all tables.
+    /// An empty array yields an empty list.
+    pub fn as_table_array(&self) -> Option<Vec<&Table>> {
+        self.as_array()?.iter().map(Value::as_table).collect()
+    }
+
     /// Extracts the table value if it is a table.
     pub fn as_table(&self) -> Option<&Table> {
         match *self {
@@ -211,6 +521,16 @@ impl Value {
         self.as_table().is_some()
     }
 
+    /// Consumes this value, returning the table if it is a table.
+    ///
+    /// Otherwise the value is handed back as `Err`.
+    pub fn into_table(self) -> Result<Table, Value> {
+        match self {
+            Value::Table(t) => Ok(t),
+            other => Err(other),
+        }
+    }
+
     /// Tests whether this and another value have the same type.
     pub fn same_type(&self, other: &Value) -> bool {
         discriminant(self) == discriminant(other)
@@ -228,8 +548,482 @@ impl Value {
             Value::Table(..) => "table",
         }
     }
+
+    /// Returns the number of scalar values, that is values which are neither
+    /// arrays nor tables, contained in this value.
+    ///
+    /// A scalar counts itself, so its leaf count is 1.
+    pub fn leaf_count(&self) -> usize {
+        match *self {
+            Value::Array(ref array) => array.iter().map(Value::leaf_count).sum(),
+            Value::Table(ref table) => table.values().map(Value::leaf_count).sum(),
+            _ => 1,
+        }
+    }
+
+    /// Returns the deepest nesting of arrays and tables in this value.
+    ///
+    /// Scalars have a depth of 0, and an array or table is one level deeper
+    /// than its deepest element, so `a.b.c = 1` has a depth of 3.
+    pub fn max_depth(&self) -> usize {
+        match *self {
+            Value::Array(ref array) => 1 + array.iter().map(Value::max_depth).max().unwrap_or(0),
+            Value::Table(ref table) => 1 + table.values().map(Value::max_depth).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Flattens this value into a map from joined key paths to leaf values.
+    ///
+    /// The keys of nested tables and the indices of arrays are joined with
+    /// `sep`, so `a.b = 1` and `c = [2, 3]` flattened with `"."` give the
+    /// entries `a.b = 1`, `c.0 = 2` and `c.1 = 3`. With a separator of `"."`
+    /// keys are quoted when needed, as in `servers."alpha.example".ip`, so
+    /// that `Value::unflatten` can split them again; with any other separator
+    /// they are joined as-is. Empty arrays and tables are kept as leaves so that
+    /// they aren't lost, and a scalar flattens to a single entry with an empty
+    /// key.
+    pub fn flatten(&self, sep: &str) -> BTreeMap<String, Value> {
+        fn flatten(
+            value: &Value,
+            path: &mut Vec<String>,
+            sep: &str,
+            out: &mut BTreeMap<String, Value>,
+        ) {
+            match *value {
+                Value::Array(ref array) if !array.is_empty() => {
+                    for (i, value) in array.iter().enumerate() {
+                        path.push(i.to_string());
+                        flatten(value, path, sep, out);
+                        path.pop();
+                    }
+                }
+                Value::Table(ref table) if !table.is_empty() => {
+                    for (key, value) in table.iter() {
+                        if sep == "." {
+                            path.push(path_segment(key));
+                        } else {
+                            path.push(key.clone());
+                        }
+                        flatten(value, path, sep, out);
+                        path.pop();
+                    }
+                }
+                _ => {
+                    out.insert(path.join(sep), value.clone());
+                }
+            }
+        }
+
+        let mut out = BTreeMap::new();
+        flatten(self, &mut Vec::new(), sep, &mut out);
+        out
+    }
+
+    /// Returns the dotted path of every scalar in this value, such as
+    /// `servers.0.host`.
+    ///
+    /// Keys are quoted when needed so that each path can be passed back to
+    /// `Value::lookup`. Array elements are named by
+    /// their index. Empty arrays and tables contain no scalars and so give no
+    /// path, while a scalar itself gives a single empty path. Paths are
+    /// listed depth first, in the iteration order of the tables.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let value: toml::Value = "a = 1\n[[servers]]\nhost = 'x'".parse().unwrap();
+    /// assert_eq!(value.leaf_paths(), ["a", "servers.0.host"]);
+    /// ```
+    pub fn leaf_paths(&self) -> Vec<String> {
+        fn leaf_paths(value: &Value, path: &mut Vec<String>, out: &mut Vec<String>) {
+            match *value {
+                Value::Array(ref array) => {
+                    for (i, value) in array.iter().enumerate() {
+                        path.push(i.to_string());
+                        leaf_paths(value, path, out);
+                        path.pop();
+                    }
+                }
+                Value::Table(ref table) => {
+                    for (key, value) in table.iter() {
+                        path.push(path_segment(key));
+                        leaf_paths(value, path, out);
+                        path.pop();
+                    }
+                }
+                _ => out.push(path.join(".")),
+            }
+        }
+
+        let mut out = Vec::new();
+        leaf_paths(self, &mut Vec::new(), &mut out);
+        out
+    }
+
+    /// Converts a table of scalars into a map of strings.
+    ///
+    /// Strings are kept as-is, without quotes, while other scalars are
+    /// rendered as they would be written in a TOML document, e.g. `1.0` or
+    /// `1979-05-27T07:32:00Z`. Returns `None` if this isn't a table or if any
+    /// of its values is an array or a table.
+    pub fn into_string_map(self) -> Option<BTreeMap<String, String>> {
+        let table = self.into_table().ok()?;
+        table
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::String(s) => Some((key, s)),
+                Value::Array(..) | Value::Table(..) => None,
+                value => Some((key, value.to_string())),
+            })
+            .collect()
+    }
+
+    /// Builds a table from pairs of joined key paths and values, the reverse
+    /// of `flatten`.
+    ///
+    /// Each key is split on `sep` and the value is stored in the nested
+    /// tables named by the segments, which are created as needed. With a
+    /// separator of `"."` the keys are split with `toml::parse_path` instead,
+    /// so segments which contain a period can be quoted, as in
+    /// `servers."alpha.example".ip`.
+    ///
+    /// Array indices can't be told apart from keys, so arrays flattened by
+    /// `flatten` come back as tables keyed by index. A single pair with an
+    /// empty key, which is what `flatten` gives for a scalar, is returned as
+    /// the value itself.
+    ///
+    /// Returns an error if a key isn't a valid path, or if a key is given a
+    /// value more than once, either directly or because it is both used as a
+    /// table and given another value, as with `a = 1` and `a.b = 2`.
+    pub fn unflatten<I>(pairs: I, sep: &str) -> Result<Value, crate::de::Error>
+    where
+        I: IntoIterator<Item = (String, Value)>,
+    {
+        let mut pairs = pairs.into_iter().peekable();
+        let mut root = Table::new();
+        while let Some((key, value)) = pairs.next() {
+            if key.is_empty() && root.is_empty() && pairs.peek().is_none() {
+                return Ok(value);
+            }
+            let segments = if sep == "." {
+                crate::de::parse_path(&key)
+                    .ok_or_else(|| de::Error::custom(format!("invalid key path: `{}`", key)))?
+            } else {
+                key.split(sep).map(|s| s.to_string()).collect()
+            };
+            let conflict = || de::Error::custom(format!("conflicting values for key `{}`", key));
+
+            let (last, parents) = segments.split_last().unwrap();
+            let mut table = &mut root;
+            for segment in parents {
+                table = table
+                    .entry(segment.clone())
+                    .or_insert_with(|| Value::Table(Table::new()))
+                    .as_table_mut()
+                    .ok_or_else(conflict)?;
+            }
+            if table.contains_key(last) {
+                return Err(conflict());
+            }
+            table.insert(last.clone(), value);
+        }
+        Ok(Value::Table(root))
+    }
+
+    /// Renders this value as a "pretty" TOML string, formatted according to
+    /// `opts`.
+    ///
+    /// Strings are emitted as with `Serializer::pretty`, while the layout of
+    /// tables and arrays is controlled by the options. The default `Display`
+    /// implementation is unaffected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this value can't be represented as a TOML document, for
+    /// example if it isn't a table.
+    pub fn to_string_pretty(&self, opts: PrettyOptions) -> String {
+        let mut dst = String::with_capacity(128);
+        {
+            let mut serializer = crate::Serializer::pretty(&mut dst);
+            match opts.array_wrap_threshold {
+                Some(threshold) => {
+                    serializer
+                        .pretty_array_indent(opts.indent)
+                        .pretty_array_threshold(threshold);
+                }
+                None => {
+                    serializer.pretty_array(false);
+                }
+            }
+            serializer.compact_tables(!opts.blank_line_between_tables);
+            ser::Serialize::serialize(self, &mut serializer)
+                .expect("Unable to represent value as string");
+        }
+        dst
+    }
+
+    /// Walks this value and everything nested inside it in pre-order, calling
+    /// `f` with the path of keys leading to each value and a mutable reference
+    /// to it.
+    ///
+    /// The root is visited with an empty path. Elements of an array are
+    /// visited with the path of the array itself. Changes made by `f` to a
+    /// value are visible when its children are walked afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut value: toml::Value = "[db]\nuser = 'admin'\npassword = 'hunter2'".parse().unwrap();
+    /// value.walk_mut(|path, value| {
+    ///     if path.last() == Some(&"password") {
+    ///         *value = toml::Value::String("***".to_string());
+    ///     }
+    /// });
+    /// assert_eq!(value["db"]["password"].as_str(), Some("***"));
+    /// ```
+    pub fn walk_mut<F: FnMut(&[&str], &mut Value)>(&mut self, mut f: F) {
+        fn walk<'a, F: FnMut(&[&str], &mut Value)>(
+            value: &'a mut Value,
+            path: &mut Vec<&'a str>,
+            f: &mut F,
+        ) {
+            f(path, value);
+            match *value {
+                Value::Array(ref mut array) => {
+                    for value in array {
+                        walk(value, path, f);
+                    }
+                }
+                Value::Table(ref mut table) => {
+                    for (key, value) in table.iter_mut() {
+                        path.push(key);
+                        walk(value, path, f);
+                        path.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        walk(self, &mut Vec::new(), &mut f)
+    }
+
+    /// Retains only the table entries for which `f` returns `true`, at every
+    /// level of nesting.
+    ///
+    /// `f` is called with the key and value of each entry, and entries of
+    /// nested tables, including tables inside of arrays, are filtered in turn
+    /// after their parent was kept. The order of the remaining entries is
+    /// preserved. Array elements themselves are never removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut value: toml::Value = "_a = 1\nb = 2\n[c]\n_d = 3".parse().unwrap();
+    /// value.retain(|key, _| !key.starts_with('_'));
+    /// assert_eq!(value.to_string(), "b = 2\n\n[c]\n");
+    /// ```
+    pub fn retain<F: FnMut(&str, &Value) -> bool>(&mut self, mut f: F) {
+        fn retain<F: FnMut(&str, &Value) -> bool>(value: &mut Value, f: &mut F) {
+            match *value {
+                Value::Array(ref mut array) => {
+                    for value in array {
+                        retain(value, f);
+                    }
+                }
+                Value::Table(ref mut table) => {
+                    *table = std::mem::take(table)
+                        .into_iter()
+                        .filter(|(key, value)| f(key, value))
+                        .collect();
+                    for (_, value) in table.iter_mut() {
+                        retain(value, f);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        retain(self, &mut f)
+    }
+
+    /// Fills in the keys of `defaults` which are missing from this value.
+    ///
+    /// Tables are merged recursively: keys of `defaults` which this table
+    /// lacks are inserted, and tables present in both get their own missing
+    /// keys filled in. Existing values are never overwritten, so a scalar or
+    /// array in `self` always wins over the default, even one of a different
+    /// type. Nothing happens unless both values are tables.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut config: toml::Value = "[a]\nb = 1".parse().unwrap();
+    /// let defaults: toml::Value = "[a]\nb = 0\nc = 2".parse().unwrap();
+    /// config.apply_defaults(&defaults);
+    /// assert_eq!(config.to_string(), "[a]\nb = 1\nc = 2\n");
+    /// ```
+    pub fn apply_defaults(&mut self, defaults: &Value) {
+        if let (Value::Table(table), Value::Table(defaults)) = (self, defaults) {
+            for (key, default) in defaults {
+                match table.get_mut(key) {
+                    Some(value) => value.apply_defaults(default),
+                    None => {
+                        table.insert(key.clone(), default.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Converts the integers of arrays which also contain floats to floats.
+    ///
+    /// TOML 1.0 allows arrays to mix integers and floats, but consumers
+    /// following older versions of the spec, which required homogeneous
+    /// arrays, may reject them. This makes such arrays homogeneous before
+    /// serialization, walking into tables and nested arrays. Arrays
+    /// holding only integers are left alone, and so are integers outside of
+    /// arrays. Integers are converted with `as`, so those with a magnitude
+    /// above 2<sup>53</sup> may lose precision.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use toml::Value;
+    ///
+    /// let mut value = Value::Array(vec![Value::Integer(1), Value::Float(2.5)]);
+    /// value.coerce_numbers();
+    /// assert_eq!(value.to_string(), "[1.0, 2.5]");
+    /// ```
+    pub fn coerce_numbers(&mut self) {
+        match *self {
+            Value::Array(ref mut array) => {
+                array.iter_mut().for_each(Value::coerce_numbers);
+                let has_float = array.iter().any(Value::is_float);
+                if has_float && array.iter().any(Value::is_integer) {
+                    for value in array {
+                        if let Value::Integer(i) = *value {
+                            *value = Value::Float(i as f64);
+                        }
+                    }
+                }
+            }
+            Value::Table(ref mut table) => table.iter_mut().for_each(|(_, v)| v.coerce_numbers()),
+            _ => {}
+        }
+    }
+
+    /// Computes the differences between this value and `other`.
+    ///
+    /// Tables are compared key by key and arrays element by element, so only
+    /// the values which actually changed are reported. A value whose type
+    /// changed is reported as `Change::Modified`, and elements past the end of
+    /// the shorter array as added or removed.
+    pub fn diff(&self, other: &Value) -> Vec<Change> {
+        fn diff(old: &Value, new: &Value, path: &mut Vec<String>, changes: &mut Vec<Change>) {
+            match (old, new) {
+                (Value::Table(old), Value::Table(new)) => {
+                    for (key, old) in old.iter() {
+                        path.push(path_segment(key));
+                        match new.get(key) {
+                            Some(new) => diff(old, new, path, changes),
+                            None => changes.push(Change::Removed(path.join("."), old.clone())),
+                        }
+                        path.pop();
+                    }
+                    for (key, new) in new.iter() {
+                        if !old.contains_key(key) {
+                            path.push(path_segment(key));
+                            changes.push(Change::Added(path.join("."), new.clone()));
+                            path.pop();
+                        }
+                    }
+                }
+                (Value::Array(old), Value::Array(new)) => {
+                    for i in 0..old.len().max(new.len()) {
+                        path.push(i.to_string());
+                        match (old.get(i), new.get(i)) {
+                            (Some(old), Some(new)) => diff(old, new, path, changes),
+                            (Some(old), None) => {
+                                changes.push(Change::Removed(path.join("."), old.clone()))
+                            }
+                            (None, Some(new)) => {
+                                changes.push(Change::Added(path.join("."), new.clone()))
+                            }
+                            (None, None) => unreachable!(),
+                        }
+                        path.pop();
+                    }
+                }
+                _ => {
+                    if old != new {
+                        changes.push(Change::Modified(path.join("."), old.clone(), new.clone()));
+                    }
+                }
+            }
+        }
+
+        let mut changes = Vec::new();
+        diff(self, other, &mut Vec::new(), &mut changes);
+        changes
+    }
+
+    /// Tests whether this and another value are structurally equal, treating
+    /// an integer and a float with the same numeric value as equal.
+    ///
+    /// Tables are compared key by key and arrays element by element; all other
+    /// values are compared with `PartialEq`.
+    pub fn loose_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Integer(i), Value::Float(f)) | (Value::Float(f), Value::Integer(i)) => {
+                *i as f64 == *f
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.loose_eq(b))
+            }
+            (Value::Table(a), Value::Table(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(k, v)| matches!(b.get(k), Some(other) if v.loose_eq(other)))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Returns the `type_str` of the first element if this value is a
+    /// non-empty array.
+    ///
+    /// Returns `None` for empty arrays and for values which aren't arrays.
+    pub fn array_element_type(&self) -> Option<&'static str> {
+        self.as_array()
+            .and_then(|array| array.first())
+            .map(|value| value.type_str())
+    }
+}
+
+/// Formats `key` as a segment of a dotted path, quoting it if it isn't a bare
+/// key.
+pub(crate) fn path_segment(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_'));
+    if bare {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
 }
 
+/// Indexes into a TOML table with a string or into an array with a `usize`,
+/// such as `config["database"]["port"]`.
+///
+/// # Panics
+///
+/// Panics if the key or index doesn't exist, or if the value isn't a table
+/// or an array matching the index. Use `Value::get` or `Value::lookup` to
+/// handle missing values instead.
 impl<I> ops::Index<I> for Value
 where
     I: Index,
@@ -241,6 +1035,10 @@ where
     }
 }
 
+/// Mutably indexes into a TOML table or array, see `Index`.
+///
+/// This panics in the same cases as indexing immutably; it doesn't insert
+/// missing keys. Use `Value::entry` to create them.
 impl<I> ops::IndexMut<I> for Value
 where
     I: Index,
@@ -271,6 +1069,18 @@ impl<S: Into<String>, V: Into<Value>> From<BTreeMap<S, V>> for Value {
     }
 }
 
+impl FromIterator<(String, Value)> for Value {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Value {
+        Value::Table(iter.into_iter().collect())
+    }
+}
+
+impl FromIterator<Value> for Value {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Value {
+        Value::Array(iter.into_iter().collect())
+    }
+}
+
 impl<S: Into<String> + Hash + Eq, V: Into<Value>> From<HashMap<S, V>> for Value {
     fn from(val: HashMap<S, V>) -> Value {
         let table = val.into_iter().map(|(s, v)| (s.into(), v.into())).collect();
@@ -279,6 +1089,62 @@ impl<S: Into<String> + Hash + Eq, V: Into<Value>> From<HashMap<S, V>> for Value
     }
 }
 
+/// Converts a JSON value into a TOML value.
+///
+/// This requires the `json` feature. Objects become tables, and numbers
+/// become integers when they fit in an `i64` and floats otherwise.
+///
+/// Fails with `ser::Error::UnsupportedNone` if the JSON value contains a
+/// `null` anywhere, as TOML has no equivalent. Note that `Value::try_from`
+/// refers to the inherent, serde-based conversion, so use `TryInto` or
+/// `TryFrom::try_from` to pick this impl.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryInto;
+///
+/// let json = serde_json::json!({ "a": [1, 2.5], "b": { "c": "x" } });
+/// let value: toml::Value = json.try_into().unwrap();
+/// assert_eq!(value.to_string(), "a = [1, 2.5]\n\n[b]\nc = \"x\"\n");
+///
+/// let json = serde_json::json!({ "a": null });
+/// assert!(TryInto::<toml::Value>::try_into(json).is_err());
+///
+/// let json = serde_json::json!({ "a": u64::MAX });
+/// assert!(TryInto::<toml::Value>::try_into(json).is_err());
+/// ```
+#[cfg(feature = "json")]
+impl std::convert::TryFrom<serde_json::Value> for Value {
+    type Error = crate::ser::Error;
+
+    fn try_from(val: serde_json::Value) -> Result<Value, crate::ser::Error> {
+        match val {
+            serde_json::Value::Null => Err(crate::ser::Error::UnsupportedNone),
+            serde_json::Value::Bool(b) => Ok(Value::Boolean(b)),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Ok(Value::Integer(i)),
+                None if n.is_u64() => Err(ser::Error::custom("u64 value was too large")),
+                None => n
+                    .as_f64()
+                    .map(Value::Float)
+                    .ok_or(crate::ser::Error::NumberInvalid),
+            },
+            serde_json::Value::String(s) => Ok(Value::String(s)),
+            serde_json::Value::Array(vec) => vec
+                .into_iter()
+                .map(std::convert::TryFrom::try_from)
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            serde_json::Value::Object(map) => map
+                .into_iter()
+                .map(|(k, v)| Ok((k, std::convert::TryFrom::try_from(v)?)))
+                .collect::<Result<_, _>>()
+                .map(Value::Table),
+        }
+    }
+}
+
 macro_rules! impl_into_value {
     ($variant:ident : $T:ty) => {
         impl From<$T> for Value {
@@ -619,9 +1485,24 @@ impl<'de> de::Deserializer<'de> for Value {
         visitor.visit_newtype_struct(self)
     }
 
+    // Unit structs are serialized as empty tables.
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, crate::de::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self {
+            Value::Table(ref table) if table.is_empty() => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
     serde::forward_to_deserialize_any! {
         bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
-        bytes byte_buf map unit_struct tuple_struct struct
+        bytes byte_buf map tuple_struct struct
         tuple ignored_any identifier
     }
 }
@@ -773,6 +1654,20 @@ impl ser::Serializer for Serializer {
         }
     }
 
+    fn serialize_i128(self, value: i128) -> Result<Value, crate::ser::Error> {
+        match i64::try_from(value) {
+            Ok(value) => self.serialize_i64(value),
+            Err(_) => Err(ser::Error::custom("i128 value was out of range")),
+        }
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<Value, crate::ser::Error> {
+        match i64::try_from(value) {
+            Ok(value) => self.serialize_i64(value),
+            Err(_) => Err(ser::Error::custom("u128 value was out of range")),
+        }
+    }
+
     fn serialize_f32(self, value: f32) -> Result<Value, crate::ser::Error> {
         self.serialize_f64(value.into())
     }
@@ -801,7 +1696,7 @@ impl ser::Serializer for Serializer {
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, crate::ser::Error> {
-        Err(crate::ser::Error::UnsupportedType)
+        Ok(Value::Table(Table::new()))
     }
 
     fn serialize_unit_variant(
@@ -919,7 +1814,11 @@ impl ser::SerializeSeq for SerializeVec {
     where
         T: ser::Serialize,
     {
-        self.vec.push(Value::try_from(value)?);
+        match Value::try_from(value) {
+            Ok(value) => self.vec.push(value),
+            Err(crate::ser::Error::UnsupportedNone) => return Err(crate::ser::Error::NoneInArray),
+            Err(e) => return Err(e),
+        }
         Ok(())
     }
 
@@ -1033,8 +1932,8 @@ impl ser::SerializeStruct for SerializeMap {
     }
 }
 
-struct DatetimeOrTable<'a> {
-    key: &'a mut String,
+pub(crate) struct DatetimeOrTable<'a> {
+    pub(crate) key: &'a mut String,
 }
 
 impl<'a, 'de> de::DeserializeSeed<'de> for DatetimeOrTable<'a> {
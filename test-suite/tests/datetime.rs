@@ -37,7 +37,16 @@ fn times() {
     good("1997-09-09T09:09:09.09+09:09");
     good("1997-09-09T09:09:09.09-09:09");
     good("1997-09-09T09:09:09.09");
+    good("1997-09-09T09:09:09+05:45");
+    good("1997-09-09T09:09:09+12:00");
+    good("1997-09-09T09:09:09-12:00");
+    good("1997-09-09T09:09:09+13:00");
+    good("1997-09-09T09:09:09+14:00");
+    good("1997-09-09T09:09:09-12:30");
     good("09:09:09.09");
+    good("2020-02-29");
+    good("2021-04-30");
+    good("2021-12-31");
 }
 
 #[test]
@@ -111,6 +120,19 @@ fn bad_times() {
         "failed to parse datetime for key `foo` at line 1 column 7"
     );
 
+    bad!(
+        "foo = 1997-09-09T09:09:09+05:60",
+        "failed to parse datetime for key `foo` at line 1 column 7"
+    );
+    bad!(
+        "foo = 1997-09-09T09:09:09+05:99",
+        "failed to parse datetime for key `foo` at line 1 column 7"
+    );
+    bad!(
+        "foo = 1997-09-09T09:09:09+24:00",
+        "failed to parse datetime for key `foo` at line 1 column 7"
+    );
+
     bad!(
         "foo = 1997-00-09T09:09:09.09Z",
         "failed to parse datetime for key `foo` at line 1 column 7"
@@ -131,4 +153,246 @@ fn bad_times() {
         "foo = 1997-09-09T12:09:69.09Z",
         "failed to parse datetime for key `foo` at line 1 column 7"
     );
+    bad!(
+        "foo = 2021-02-29",
+        "failed to parse datetime for key `foo` at line 1 column 7"
+    );
+    bad!(
+        "foo = 2021-02-30T09:09:09Z",
+        "failed to parse datetime for key `foo` at line 1 column 7"
+    );
+    bad!(
+        "foo = 2021-04-31",
+        "failed to parse datetime for key `foo` at line 1 column 7"
+    );
+}
+
+#[test]
+fn leap_years() {
+    fn parses(date: &str) -> bool {
+        format!("foo = {}", date).parse::<toml::Value>().is_ok()
+    }
+
+    assert!(!parses("1900-02-29"));
+    assert!(parses("1900-02-28"));
+    assert!(parses("2000-02-29"));
+    assert!(parses("2020-02-29"));
+    assert!(!parses("2100-02-29"));
+    assert!(parses("2100-02-28"));
+}
+
+#[test]
+fn checked_add() {
+    fn add_minutes(dt: &str, minutes: i64) -> Option<String> {
+        let dt: toml::value::Datetime = dt.parse().unwrap();
+        dt.checked_add_minutes(minutes).map(|d| d.to_string())
+    }
+
+    assert_eq!(
+        add_minutes("2021-02-28T23:30:00+05:45", 45).as_deref(),
+        Some("2021-03-01T00:15:00+05:45")
+    );
+    assert_eq!(
+        add_minutes("2020-02-28T23:30:00", 45).as_deref(),
+        Some("2020-02-29T00:15:00")
+    );
+    assert_eq!(
+        add_minutes("2021-01-01T00:15:00.5Z", -30).as_deref(),
+        Some("2020-12-31T23:45:00.5Z")
+    );
+    assert_eq!(
+        add_minutes("2021-01-01T00:00:00Z", 365 * 24 * 60).as_deref(),
+        Some("2022-01-01T00:00:00Z")
+    );
+    assert_eq!(add_minutes("9999-12-31T23:59:00Z", 1), None);
+    assert_eq!(add_minutes("0000-01-01T00:00:00Z", -1), None);
+    assert_eq!(add_minutes("23:30:00", 45), None);
+    assert_eq!(add_minutes("2021-01-01", 45), None);
+    assert_eq!(add_minutes("2021-01-01T00:00:00Z", i64::MAX), None);
+
+    let dt: toml::value::Datetime = "1979-05-27T07:32:00Z".parse().unwrap();
+    assert_eq!(
+        dt.checked_add_seconds(-86_401).unwrap().to_string(),
+        "1979-05-26T07:31:59Z"
+    );
+}
+
+#[test]
+fn to_rfc3339() {
+    use toml::value::{Datetime, Offset};
+
+    for s in &[
+        "1979-05-27T07:32:00Z",
+        "1979-05-27T07:32:00.5-07:00",
+        "1979-05-27T07:32:00",
+        "1979-05-27",
+        "07:32:00",
+    ] {
+        let dt: Datetime = s.parse().unwrap();
+        assert_eq!(dt.to_rfc3339().as_deref(), Some(*s));
+    }
+
+    let mut dt: Datetime = "1979-05-27T07:32:00Z".parse().unwrap();
+    dt.time = None;
+    assert_eq!(dt.to_rfc3339(), None);
+    dt.date = None;
+    assert_eq!(dt.to_rfc3339(), None);
+    dt.offset = None;
+    assert_eq!(dt.to_rfc3339(), None);
+
+    let mut dt: Datetime = "07:32:00".parse().unwrap();
+    dt.offset = Some(Offset::Z);
+    assert_eq!(dt.to_rfc3339(), None);
+}
+
+#[test]
+fn from_str_strict() {
+    use toml::value::Datetime;
+
+    for s in &[
+        "1979-05-27T07:32:00Z",
+        "1979-05-27T07:32:00.999-07:00",
+        "1979-05-27T07:32:00.123456789",
+        "1979-05-27",
+        "07:32:00.000",
+    ] {
+        let dt = Datetime::from_str_strict(s).unwrap();
+        assert_eq!(dt, s.parse().unwrap());
+    }
+
+    for s in &[
+        "1979-05-27T07:32:00.9Z",
+        "1979-05-27T07:32:00.99",
+        "07:32:00.1234567891",
+        "07:32:00.",
+    ] {
+        assert!(Datetime::from_str_strict(s).is_err(), "{}", s);
+    }
+    assert!("07:32:00.1234567891".parse::<Datetime>().is_ok());
+}
+
+#[test]
+fn same_instant() {
+    use toml::value::Datetime;
+
+    let dt = |s: &str| s.parse::<Datetime>().unwrap();
+
+    let a = dt("1979-05-27T00:32:00-07:00");
+    let b = dt("1979-05-27T07:32:00Z");
+    assert!(a != b);
+    assert!(a.same_instant(&b));
+    assert!(b.same_instant(&a));
+    assert!(dt("1979-05-26T23:00:00-09:30").same_instant(&dt("1979-05-27T10:00:00+01:30")));
+    assert!(dt("2000-01-01T00:00:00+01:00").same_instant(&dt("1999-12-31T23:00:00Z")));
+
+    assert!(!a.same_instant(&dt("1979-05-27T07:32:00.5Z")));
+    assert!(!a.same_instant(&dt("1979-05-27T07:33:00Z")));
+
+    let local = dt("1979-05-27T07:32:00");
+    assert!(!local.same_instant(&local));
+    assert!(!b.same_instant(&local));
+    assert!(!dt("1979-05-27").same_instant(&dt("1979-05-27")));
+}
+
+#[test]
+fn cmp_local() {
+    use std::cmp::Ordering::*;
+    use toml::value::Datetime;
+
+    let dt = |s: &str| s.parse::<Datetime>().unwrap();
+    let cmp = |a: &str, b: &str| dt(a).cmp_local(&dt(b));
+
+    assert_eq!(
+        cmp("1979-05-27T07:32:00", "1979-05-27T07:32:00"),
+        Some(Equal)
+    );
+    assert_eq!(
+        cmp("1979-05-27T23:59:59", "1979-05-28T00:00:00"),
+        Some(Less)
+    );
+    assert_eq!(
+        cmp("1979-05-27T07:32:00.5", "1979-05-27T07:32:00"),
+        Some(Greater)
+    );
+    assert_eq!(cmp("1979-05-27", "1979-12-01"), Some(Less));
+    assert_eq!(cmp("2000-01-01", "1999-12-31"), Some(Greater));
+    assert_eq!(cmp("07:32:00", "00:32:00"), Some(Greater));
+    assert_eq!(
+        cmp("1979-05-27T00:32:00-07:00", "1979-05-27T07:00:00Z"),
+        Some(Greater)
+    );
+    assert_eq!(
+        cmp("1979-05-27T00:32:00-07:00", "1979-05-27T07:32:00Z"),
+        Some(Equal)
+    );
+
+    assert_eq!(cmp("1979-05-27", "1979-05-27T00:00:00"), None);
+    assert_eq!(cmp("07:32:00", "1979-05-27T07:32:00"), None);
+    assert_eq!(cmp("1979-05-27T07:32:00", "1979-05-27T07:32:00Z"), None);
+}
+
+#[test]
+fn normalize_utc_offset() {
+    use toml::value::Datetime;
+
+    let normalized = |s: &str| {
+        let mut dt = s.parse::<Datetime>().unwrap();
+        dt.normalize_utc_offset();
+        dt.to_string()
+    };
+
+    assert_eq!(
+        normalized("1979-05-27T07:32:00+00:00"),
+        "1979-05-27T07:32:00Z"
+    );
+    assert_eq!(
+        normalized("1979-05-27T07:32:00-00:00"),
+        "1979-05-27T07:32:00Z"
+    );
+    assert_eq!(normalized("1979-05-27T07:32:00Z"), "1979-05-27T07:32:00Z");
+    assert_eq!(
+        normalized("1979-05-27T07:32:00+00:30"),
+        "1979-05-27T07:32:00+00:30"
+    );
+    assert_eq!(normalized("1979-05-27T07:32:00"), "1979-05-27T07:32:00");
+
+    let written = "1979-05-27T07:32:00+00:00".parse::<Datetime>().unwrap();
+    assert_eq!(written.to_string(), "1979-05-27T07:32:00+00:00");
+}
+
+#[test]
+fn nanosecond_round_trip() {
+    use toml::value::{Datetime, Time};
+
+    let cases = [
+        ("00:00:00.000000001", 1),
+        ("00:00:00.120", 120_000_000),
+        ("00:00:00.999999999", 999_999_999),
+        ("00:00:00.0000000019", 1),
+        ("00:00:00.000", 0),
+    ];
+    for &(s, nanosecond) in &cases {
+        let dt = s.parse::<Datetime>().unwrap();
+        assert_eq!(dt.time.as_ref().unwrap().nanosecond, nanosecond, "{}", s);
+
+        let mut table = std::collections::BTreeMap::new();
+        table.insert("t", dt.clone());
+        let doc = toml::to_string(&table).unwrap();
+        let back: std::collections::BTreeMap<String, Datetime> = toml::from_str(&doc).unwrap();
+        assert_eq!(back["t"], dt, "{}", doc);
+    }
+
+    let time = |nanosecond| Datetime {
+        date: None,
+        time: Some(Time {
+            hour: 0,
+            minute: 0,
+            second: 0,
+            nanosecond,
+        }),
+        offset: None,
+    };
+    assert_eq!(time(1).to_string(), "00:00:00.000000001");
+    assert_eq!(time(120_000_000).to_string(), "00:00:00.12");
+    assert_eq!(time(0).to_string(), "00:00:00");
 }
@@ -133,7 +133,7 @@ fn custom_errors() {
             ]
         ",
         Parent<CasedString>,
-        "invalid value: string \"*\", expected all lowercase or all uppercase for key `p_b` at line 4 column 35"
+        "invalid value: string \"*\", expected all lowercase or all uppercase for key `p_b.c_b` at line 4 column 35"
     );
 
     // Sub-table in Vec is missing a field.
@@ -177,7 +177,7 @@ fn custom_errors() {
             ]
         ",
         Parent<CasedString>,
-        "invalid type: integer `1`, expected a string for key `p_b` at line 5 column 36"
+        "invalid type: integer `1`, expected a string for key `p_b.c_b` at line 5 column 36"
     );
 
     // Sub-table in the middle of a Vec has an extra field.
@@ -295,7 +295,7 @@ fn serde_derive_deserialize_errors() {
             ]
         ",
         Parent<String>,
-        "invalid type: integer `1`, expected a string for key `p_b` at line 4 column 34"
+        "invalid type: integer `1`, expected a string for key `p_b.c_b` at line 4 column 34"
     );
 
     // FIXME: This location could be better.
@@ -320,7 +320,7 @@ fn serde_derive_deserialize_errors() {
             ]
         ",
         Parent<String>,
-        "invalid type: integer `1`, expected a string for key `p_b` at line 4 column 34"
+        "invalid type: integer `1`, expected a string for key `p_b.c_b` at line 4 column 34"
     );
 }
 
@@ -334,7 +334,7 @@ fn error_handles_crlf() {
          a = 2\r\n\
          ",
         toml::Value,
-        "duplicate key: `a` for key `t2` at line 3 column 1"
+        "duplicate key: `a` for key `t2` (previously defined at line 4) at line 5 column 1"
     );
 
     // Should be the same as above.
@@ -346,6 +346,76 @@ fn error_handles_crlf() {
          a = 2\n\
          ",
         toml::Value,
-        "duplicate key: `a` for key `t2` at line 3 column 1"
+        "duplicate key: `a` for key `t2` (previously defined at line 4) at line 5 column 1"
     );
 }
+
+#[test]
+fn nested_key_context() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Config {
+        s3_region: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct App {
+        config: Config,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Root {
+        app: App,
+    }
+
+    bad!(
+        "app = { config = { region = 'a' } }",
+        Root,
+        "missing field `s3_region` for key `app.config` at line 1 column 18"
+    );
+
+    bad!(
+        "app.config.region = 'a'",
+        Root,
+        "missing field `s3_region` for key `app.config` at line 1 column 21"
+    );
+
+    bad!(
+        "
+            [app]
+            config = { s3_region = 1 }
+        ",
+        Root,
+        "invalid type: integer `1`, expected a string for key `app.config.s3_region` at line 3 column 36"
+    );
+}
+
+#[test]
+fn error_kind() {
+    use toml::de::ErrorKind;
+
+    let err = toml::from_str::<toml::Value>("a = 1\na = 2").unwrap_err();
+    assert_eq!(
+        *err.kind(),
+        ErrorKind::DuplicateKey {
+            key: "a".to_string(),
+            first_line: 0,
+        }
+    );
+
+    let err = toml::from_str::<toml::Value>("a = 01").unwrap_err();
+    assert_eq!(*err.kind(), ErrorKind::NumberInvalid);
+
+    let err = toml::from_str::<toml::Value>("a = \nb = 1").unwrap_err();
+    match *err.kind() {
+        ErrorKind::Wanted { expected, found } => {
+            assert_eq!((expected, found), ("a value", "a newline"));
+        }
+        ref kind => panic!("unexpected kind: {:?}", kind),
+    }
+
+    let err = toml::from_str::<Parent<i32>>("p_a = 'a'\np_b = []").unwrap_err();
+    assert_eq!(*err.kind(), ErrorKind::Custom);
+}
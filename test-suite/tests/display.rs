@@ -115,3 +115,15 @@ fn table() {
          test2 = 2\n"
     );
 }
+
+#[test]
+fn quoted_table_header() {
+    let value = Table(map! {
+        "weird key.with dot" => Table(map! {
+            "a" => Integer(1)
+        })
+    });
+    let s = value.to_string();
+    assert_eq!(s, "[\"weird key.with dot\"]\na = 1\n");
+    assert_eq!(s.parse::<toml::Value>().unwrap(), value);
+}
@@ -37,7 +37,7 @@ fn bad() {
         "a.b.c = 1
          a.b = 2
         ",
-        "duplicate key: `b` for key `a` at line 1 column 9"
+        "duplicate key: `b` for key `a` (previously defined at line 1) at line 2 column 12"
     );
     bad!(
         "a = 1
@@ -49,3 +49,35 @@ fn bad() {
         "dotted key attempted to extend non-table type at line 1 column 11"
     );
 }
+
+#[test]
+fn array_of_values_as_array_of_tables() {
+    bad!(
+        "a = [1, 2]\n[[a]]\nb = 1",
+        "`a` is already an array of values, cannot use as array of tables at line 2 column 1"
+    );
+    bad!(
+        "[t]\na = [1]\n[[t.a]]",
+        "`t.a` is already an array of values, cannot use as array of tables at line 3 column 1"
+    );
+    bad!(
+        "[[t.a]]\nb = 1\n[t]\na = [1]",
+        "`t.a` is already an array of values, cannot use as array of tables at line 4 column 5"
+    );
+    bad!(
+        "t.a = [{ b = 1 }]\n[[t.a]]",
+        "`t.a` is already an array of values, cannot use as array of tables at line 2 column 1"
+    );
+    bad!(
+        "[[x]]\na = [1]\n[[x.a]]",
+        "`x.a` is already an array of values, cannot use as array of tables at line 3 column 1"
+    );
+
+    // Each element of an array of tables has its own keys.
+    "[[x]]\na = [1]\n[[x]]\n[[x.a]]"
+        .parse::<toml::Value>()
+        .unwrap();
+    "[[x]]\n[[x.a]]\n[[x]]\na = [1]"
+        .parse::<toml::Value>()
+        .unwrap();
+}
@@ -253,11 +253,11 @@ fn bad_leading_zeros() {
     bad!("a = +00.0", "invalid number at line 1 column 7");
     bad!(
         "a = 9223372036854775808",
-        "invalid number at line 1 column 5"
+        "integer literal out of range for a 64-bit signed integer at line 1 column 5"
     );
     bad!(
         "a = -9223372036854775809",
-        "invalid number at line 1 column 5"
+        "integer literal out of range for a 64-bit signed integer at line 1 column 5"
     );
 }
 
@@ -272,6 +272,71 @@ fn bad_floats() {
     bad!("a = 0.0e+", "invalid number at line 1 column 5");
 }
 
+#[test]
+fn integer_overflow() {
+    bad!(
+        "a = 0x1_0000_0000_0000_0000",
+        "integer literal out of range for a 64-bit signed integer at line 1 column 7"
+    );
+    bad!(
+        "a = 99999999999999999999_",
+        "invalid number at line 1 column 5"
+    );
+    bad!("a = 1__0", "invalid number at line 1 column 5");
+}
+
+#[test]
+fn integer_to_float_fallback() {
+    use serde::Deserialize;
+
+    fn parse(s: &str) -> Result<Value, String> {
+        let mut d = toml::Deserializer::new(s);
+        d.set_integer_to_float_fallback(true);
+        Value::deserialize(&mut d).map_err(|e| e.to_string())
+    }
+
+    let value = parse("a = 18446744073709551615\nb = -9_223_372_036_854_775_809").unwrap();
+    assert_eq!(value["a"].as_float(), Some(18446744073709551615.0));
+    assert_eq!(value["b"].as_float(), Some(-9223372036854775809.0));
+    let value = parse("a = +9223372036854775808\nb = 0x1_0000_0000_0000_0000").unwrap();
+    assert_eq!(value["a"].as_float(), Some(9223372036854775808.0));
+    assert_eq!(value["b"].as_float(), Some(18446744073709551616.0));
+
+    // Integers which fit are unaffected, as are invalid ones.
+    let value = parse("a = 9223372036854775807\nb = [1]").unwrap();
+    assert_eq!(value["a"].as_integer(), Some(i64::MAX));
+    assert_eq!(value["b"][0].as_integer(), Some(1));
+    assert_eq!(
+        parse("a = 99999999999999999999_").unwrap_err(),
+        "invalid number at line 1 column 5"
+    );
+
+    bad!(
+        "a = 18446744073709551615",
+        "integer literal out of range for a 64-bit signed integer at line 1 column 5"
+    );
+}
+
+#[test]
+fn bad_leading_plus() {
+    bad!(
+        "x = +",
+        "expected a number after `+`, found eof at line 1 column 6"
+    );
+    bad!(
+        "x = + 1",
+        "expected a number after `+`, found whitespace at line 1 column 6"
+    );
+    bad!(
+        "x = +foo",
+        "expected a number after `+`, found an identifier at line 1 column 6"
+    );
+    bad!(
+        "x = +'1'",
+        "expected a number after `+`, found a string at line 1 column 6"
+    );
+}
+
 #[test]
 fn floats() {
     macro_rules! t {
@@ -454,7 +519,11 @@ fn inline_tables() {
     );
     bad!(
         "a = {a=1,a=1}",
-        "duplicate key: `a` for key `a` at line 1 column 5"
+        "duplicate key: `a` for key `a` (previously defined at line 1) at line 1 column 10"
+    );
+    bad!(
+        "[t]\nx.y = {a={b=1,b=2}}",
+        "duplicate key: `b` for key `t.x.y.a` (previously defined at line 2) at line 2 column 15"
     );
     bad!(
         "a = {\n}",
@@ -564,7 +633,7 @@ fn bad_nesting() {
         [[a]]
         b = 5
         ",
-        "duplicate key: `a` at line 3 column 9"
+        "`a` is already an array of values, cannot use as array of tables at line 3 column 9"
     );
     bad!(
         "
@@ -711,3 +780,235 @@ fn require_newline_after_value() {
         "invalid number at line 2 column 3"
     );
 }
+
+#[test]
+fn parse_recover() {
+    let mut d = toml::Deserializer::new("a = 1\nb = 01\nc = \"ok\"\nd = \n[e]\nf = 1\nf = 2\n");
+    let errors = d.parse_recover().unwrap_err();
+    let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            "invalid number at line 2 column 6",
+            "expected a value, found a newline at line 4 column 5",
+            "duplicate key: `f` for key `e` (previously defined at line 6) at line 7 column 1",
+        ]
+    );
+
+    // The remaining lines of a multi-line array aren't read as new lines
+    let mut d = toml::Deserializer::new("a = [1,\n 2 x]\nb = 1\nc = {\n}\n");
+    let errors = d.parse_recover().unwrap_err();
+    let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            "expected a right bracket, found an identifier at line 2 column 4",
+            "expected a table key, found a newline at line 4 column 6",
+        ]
+    );
+
+    let mut d = toml::Deserializer::new("a = 1\n[b]\nc = [1, 2]\n");
+    d.parse_recover().unwrap();
+}
+
+#[test]
+fn trailing_garbage() {
+    bad!(
+        "a = 1\n} garbage",
+        "expected a table key, found a right brace at line 2 column 1"
+    );
+
+    // A type which doesn't consume the document still requires it to be
+    // empty.
+    struct Nothing;
+
+    impl<'de> serde::Deserialize<'de> for Nothing {
+        fn deserialize<D: serde::Deserializer<'de>>(_: D) -> Result<Nothing, D::Error> {
+            Ok(Nothing)
+        }
+    }
+
+    assert!(toml::from_str::<Nothing>("\n# comment\n").is_ok());
+    match toml::from_str::<Nothing>("\n  a = 1") {
+        Ok(_) => panic!("successful"),
+        Err(e) => assert_eq!(
+            e.to_string(),
+            "unexpected content after end of document at line 2 column 3"
+        ),
+    }
+}
+
+#[test]
+fn with_offset() {
+    use serde::Deserialize;
+
+    let doc = "+++\na = 1\nb = 01\n+++\nbody\n";
+    let base = doc.find("a = 1").unwrap();
+    let toml = &doc[base..doc.rfind("+++").unwrap()];
+
+    let mut d = toml::Deserializer::with_offset(toml, base);
+    let err = Value::deserialize(&mut d).unwrap_err();
+    assert_eq!(err.offset(), Some(doc.find("01").unwrap() + 1));
+    assert_eq!(err.line_col(), Some((1, 5)));
+
+    let mut d = toml::Deserializer::new(toml);
+    let err = Value::deserialize(&mut d).unwrap_err();
+    assert_eq!(err.offset(), Some(toml.find("01").unwrap() + 1));
+}
+
+#[test]
+fn strict_duplicates() {
+    use serde::Deserialize;
+
+    fn parse(s: &str) -> Result<Value, String> {
+        let mut d = toml::Deserializer::new(s);
+        d.set_strict_duplicates(true);
+        Value::deserialize(&mut d).map_err(|e| e.to_string())
+    }
+
+    let s = "[a.b]\nc = 1\n[a]\nd = 2\n";
+    assert!(s.parse::<Value>().is_ok());
+    assert_eq!(
+        parse(s).unwrap_err(),
+        "redefinition of table `a` at line 3 column 1"
+    );
+    assert_eq!(
+        parse("[[x]]\n[x.a.b]\n[x.a]\n").unwrap_err(),
+        "redefinition of table `x.a` at line 3 column 1"
+    );
+
+    assert!(parse("[a]\nd = 2\n[a.b]\nc = 1\n").is_ok());
+    assert!(parse("[[x]]\n[x.a.b]\n[[x]]\n[x.a]\n").is_ok());
+    assert!(parse("[[x.y]]\n[[x.y]]\n[x.z]\n").is_ok());
+}
+
+#[test]
+fn hex_escapes() {
+    use serde::Deserialize;
+
+    fn parse(s: &str) -> Result<Value, String> {
+        let mut d = toml::Deserializer::new(s);
+        d.set_hex_escapes(true);
+        Value::deserialize(&mut d).map_err(|e| e.to_string())
+    }
+
+    let value = parse(r#"a = "\x41\x7e\xe9" "#).unwrap();
+    assert_eq!(value["a"].as_str(), Some("A~\u{e9}"));
+    let value = parse("\"\\x5f\" = \"\"\"\n\\x0A\"\"\"").unwrap();
+    assert_eq!(value["_"].as_str(), Some("\n"));
+    assert_eq!(parse(r#"a = '\x41'"#).unwrap()["a"].as_str(), Some(r"\x41"));
+    assert_eq!(
+        parse(r#"a = "\x4""#).unwrap_err(),
+        "invalid hex escape character in string: `\\\"` at line 1 column 9"
+    );
+
+    bad!(
+        r#"a = "\x41""#,
+        "invalid escape character in string: `x` at line 1 column 7"
+    );
+}
+
+#[test]
+fn leading_directive() {
+    use toml::de::leading_directive;
+
+    let doc = "# toml-version: 1.0\r\n# other\r\na = 1\r\n";
+    assert_eq!(leading_directive(doc), Some("# toml-version: 1.0"));
+    assert_eq!(
+        leading_directive("\u{feff}#!/bin/tool"),
+        Some("#!/bin/tool")
+    );
+    assert_eq!(leading_directive("#"), Some("#"));
+    assert_eq!(leading_directive(" # indented"), None);
+    assert_eq!(leading_directive("\n# second line"), None);
+    assert_eq!(leading_directive("a = 1 # trailing"), None);
+    assert_eq!(leading_directive(""), None);
+    assert!(doc.parse::<Value>().is_ok());
+}
+
+#[test]
+fn unescape() {
+    use std::borrow::Cow;
+
+    fn unescape(s: &str) -> Result<Cow<'_, str>, String> {
+        toml::unescape(s).map_err(|e| e.to_string())
+    }
+
+    assert_eq!(
+        unescape(r#""a\tb\u00e9""#),
+        Ok(Cow::Owned("a\tbé".to_string()))
+    );
+    assert_eq!(unescape(r#""plain""#), Ok(Cow::Borrowed("plain")));
+    assert_eq!(unescape(r"'C:\dir'"), Ok(Cow::Borrowed(r"C:\dir")));
+    assert_eq!(unescape("'''\nline'''"), Ok(Cow::Borrowed("line")));
+    assert_eq!(
+        unescape("\"\"\"a\\\n  b\"\"\""),
+        Ok(Cow::Owned("ab".to_string()))
+    );
+
+    assert_eq!(
+        unescape(r#""\q""#),
+        Err("invalid escape character in string: `q` at line 1 column 3".to_string())
+    );
+    assert_eq!(
+        unescape("abc"),
+        Err("expected a string, found an identifier at line 1 column 1".to_string())
+    );
+    assert_eq!(
+        unescape(""),
+        Err("expected a string, found eof at line 1 column 1".to_string())
+    );
+    assert_eq!(
+        unescape(r#""a" "b""#),
+        Err("expected nothing after the string, found whitespace at line 1 column 4".to_string())
+    );
+}
+
+#[test]
+fn leading_bom() {
+    let value: Value = "\u{feff}a = 1".parse().unwrap();
+    assert_eq!(value["a"].as_integer(), Some(1));
+
+    let value: Value = "a = \"\u{feff}x\"".parse().unwrap();
+    assert_eq!(value["a"].as_str(), Some("\u{feff}x"));
+
+    assert!("a = 1\n\u{feff}b = 2".parse::<Value>().is_err());
+    assert!("\u{feff}\u{feff}a = 1".parse::<Value>().is_err());
+}
+
+#[test]
+fn quotes_next_to_delimiters() {
+    let value = |s: &str| {
+        let doc = format!("a = {}", s);
+        doc.parse::<Value>()
+            .map(|v| v["a"].as_str().unwrap().to_string())
+            .map_err(|e| e.to_string())
+    };
+
+    let q = |n: usize| "\"".repeat(n);
+    let ok = |s: &str| Ok(s.to_string());
+    assert_eq!(value(&format!("{}foo{}", q(4), q(4))), ok("\"foo\""));
+    assert_eq!(value(&format!("{}foo{}", q(5), q(5))), ok("\"\"foo\"\""));
+    assert_eq!(value(&q(7)), ok("\""));
+    assert_eq!(value(&q(6)), ok(""));
+    assert_eq!(value("''''foo''''"), ok("'foo'"));
+    assert_eq!(value("'''''foo'''''"), ok("''foo''"));
+
+    // Any further quote ends the string and starts a new one.
+    assert_eq!(
+        value(&format!("{}foo{}", q(6), q(3))),
+        Err("expected newline, found an identifier at line 1 column 11".to_string())
+    );
+    assert_eq!(
+        value(&format!("{}foo{}", q(3), q(6))),
+        Err("unterminated string at line 1 column 16".to_string())
+    );
+    assert_eq!(
+        value(&q(4)),
+        Err("unterminated string at line 1 column 5".to_string())
+    );
+    assert_eq!(
+        value("''''"),
+        Err("unterminated string at line 1 column 5".to_string())
+    );
+}
@@ -151,6 +151,29 @@ fn pretty_indent_array_no_comma() {
     assert_eq!(toml, &result);
 }
 
+const PRETTY_ARRAY_THRESHOLD: &'static str = "\
+[example]
+long = [
+    1,
+    2,
+    3,
+]
+short = [1, 2]
+";
+#[test]
+fn pretty_array_threshold() {
+    let toml = PRETTY_ARRAY_THRESHOLD;
+    let value: toml::Value = toml::from_str(toml).unwrap();
+    let mut result = String::with_capacity(128);
+    {
+        let mut serializer = toml::Serializer::new(&mut result);
+        serializer.pretty_array_threshold(2);
+        value.serialize(&mut serializer).unwrap();
+    }
+    assert_eq!(toml, &result);
+    assert_eq!(value, toml::from_str(&result).unwrap());
+}
+
 const PRETTY_NO_STRING: &'static str = "\
 [example]
 array = [
@@ -312,3 +335,192 @@ fn pretty_tricky_non_literal() {
     println!("\nRESULT:\n{}", result);
     assert_eq!(toml, &result);
 }
+
+const ALWAYS_QUOTE_KEYS: &'static str = "\
+\"name\" = \"foo\"
+
+[\"dependencies\"]
+\"serde\" = \"1.0\"
+";
+
+#[test]
+fn always_quote_keys() {
+    let toml = ALWAYS_QUOTE_KEYS;
+    let value: toml::Value = toml::from_str(toml).unwrap();
+    let mut result = String::with_capacity(128);
+    {
+        let mut serializer = toml::Serializer::new(&mut result);
+        serializer.always_quote_keys(true);
+        value.serialize(&mut serializer).unwrap();
+    }
+    println!("EXPECTED:\n{}", toml);
+    println!("\nRESULT:\n{}", result);
+    assert_eq!(toml, &result);
+}
+
+const FLOAT_PRECISION: &'static str = "\
+a = 0.3
+b = 1230.0
+c = 2.0
+d = inf
+e = -0.0
+";
+
+#[test]
+fn float_precision() {
+    let value: toml::Value =
+        toml::from_str("a = 0.30000000000000004\nb = 1234.5\nc = 2.0\nd = inf\ne = -0.0\n")
+            .unwrap();
+    let mut result = String::with_capacity(128);
+    {
+        let mut serializer = toml::Serializer::new(&mut result);
+        serializer.float_precision(Some(3));
+        value.serialize(&mut serializer).unwrap();
+    }
+    assert_eq!(FLOAT_PRECISION, &result);
+    toml::from_str::<toml::Value>(&result).unwrap();
+}
+
+const PREFER_LITERAL_STRINGS: &'static str = r#""\\e" = 'f\g'
+a = 'C:\Users\toml'
+b = "C:\\it's"
+c = "plain"
+d = "a\\b\nc"
+"#;
+
+#[test]
+fn prefer_literal_strings() {
+    let toml = PREFER_LITERAL_STRINGS;
+    let value: toml::Value = toml::from_str(toml).unwrap();
+    let mut result = String::with_capacity(128);
+    {
+        let mut serializer = toml::Serializer::new(&mut result);
+        serializer.prefer_literal_strings(true);
+        value.serialize(&mut serializer).unwrap();
+    }
+    println!("EXPECTED:\n{}", toml);
+    println!("\nRESULT:\n{}", result);
+    assert_eq!(toml, &result);
+    assert_eq!(toml::from_str::<toml::Value>(&result).unwrap(), value);
+}
+
+const SORT_KEYS_BY: &str = "\
+name = \"root\"
+alpha = 1
+zeta = [1, 2]
+
+[[items]]
+name = \"first\"
+count = 1
+
+[nested]
+name = \"inner\"
+beta = true
+
+[nested.deeper]
+name = \"deepest\"
+";
+
+#[test]
+fn sort_keys_by() {
+    let toml = SORT_KEYS_BY;
+    let value: toml::Value = toml::from_str(toml).unwrap();
+    let mut result = String::with_capacity(128);
+    {
+        let mut serializer = toml::Serializer::new(&mut result);
+        serializer.sort_keys_by(|a, b| (a != "name", a).cmp(&(b != "name", b)));
+        value.serialize(&mut serializer).unwrap();
+    }
+    println!("EXPECTED:\n{}", toml);
+    println!("\nRESULT:\n{}", result);
+    assert_eq!(toml, &result);
+
+    // The other settings still apply to the collected entries
+    let mut nested = std::collections::BTreeMap::new();
+    nested.insert("b", vec![Some(1), None, Some(2)]);
+    nested.insert("a", vec![None]);
+    let mut value = std::collections::BTreeMap::new();
+    value.insert("table", nested);
+    let mut result = String::with_capacity(128);
+    {
+        let mut serializer = toml::Serializer::new(&mut result);
+        serializer.sort_keys_by(|a, b| b.cmp(a)).skip_none(true);
+        value.serialize(&mut serializer).unwrap();
+    }
+    assert_eq!(result, "[table]\nb = [1, 2]\na = []\n");
+
+    // Floats are emitted at the precision they were serialized with
+    let mut value = std::collections::BTreeMap::new();
+    value.insert("x", 0.1f32);
+    value.insert("y", 1.5f32);
+    let mut result = String::with_capacity(128);
+    {
+        let mut serializer = toml::Serializer::new(&mut result);
+        serializer.sort_keys_by(|a, b| b.cmp(a));
+        value.serialize(&mut serializer).unwrap();
+    }
+    assert_eq!(result, "y = 1.5\nx = 0.1\n");
+}
+
+#[test]
+fn crlf_line_ending() {
+    let toml = PRETTY_STD;
+    let value: toml::Value = toml::from_str(toml).unwrap();
+    let mut result = String::with_capacity(128);
+    {
+        let mut serializer = toml::Serializer::pretty(&mut result);
+        serializer.line_ending(toml::ser::LineEnding::Crlf);
+        value.serialize(&mut serializer).unwrap();
+    }
+    println!("RESULT:\n{}", result);
+    assert_eq!(toml.replace('\n', "\r\n"), result);
+    assert_eq!(toml::from_str::<toml::Value>(&result).unwrap(), value);
+
+    let value: toml::Value = toml::from_str(NO_PRETTY).unwrap();
+    let mut result = String::with_capacity(128);
+    {
+        let mut serializer = toml::Serializer::new(&mut result);
+        serializer.line_ending(toml::ser::LineEnding::Crlf);
+        value.serialize(&mut serializer).unwrap();
+    }
+    assert_eq!(NO_PRETTY.replace('\n', "\r\n"), result);
+    assert_eq!(toml::from_str::<toml::Value>(&result).unwrap(), value);
+}
+
+#[test]
+fn to_string_with() {
+    use toml::ser::{LineEnding, SerializeOptions};
+
+    let value: toml::Value = toml::from_str(PRETTY_STD).unwrap();
+    assert_eq!(
+        toml::to_string_with(&value, &SerializeOptions::default()).unwrap(),
+        toml::to_string(&value).unwrap()
+    );
+    assert_eq!(
+        toml::to_string_with(&value, &SerializeOptions::pretty()).unwrap(),
+        PRETTY_STD
+    );
+
+    let opts = SerializeOptions::pretty()
+        .pretty_array_indent(2)
+        .pretty_array_threshold(2)
+        .compact_tables(true)
+        .line_ending(LineEnding::Crlf)
+        .sort_keys_by(|a, b| b.cmp(a));
+    let value: toml::Value = "a = [1, 2, 3]\nb = [4, 5]\n[c]\nd = 1\n[e]\nf = 1"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        toml::to_string_with(&value, &opts).unwrap(),
+        "b = [4, 5]\r\na = [\r\n  1,\r\n  2,\r\n  3,\r\n]\r\n[e]\r\nf = 1\r\n[c]\r\nd = 1\r\n"
+    );
+
+    let first = String::from("b");
+    let opts =
+        SerializeOptions::default().sort_keys_by(move |a, b| (a != first, a).cmp(&(b != first, b)));
+    let value: toml::Value = "a = 1\nb = 2\nc = 3".parse().unwrap();
+    assert_eq!(
+        toml::to_string_with(&value, &opts).unwrap(),
+        "b = 2\na = 1\nc = 3\n"
+    );
+}
@@ -4,7 +4,7 @@ extern crate toml;
 #[macro_use]
 extern crate serde_derive;
 
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::{BTreeMap, HashSet};
 
 use toml::map::Map;
@@ -681,3 +681,774 @@ fn json_interoperability() {
     )
     .unwrap();
 }
+
+#[test]
+fn numbers_from_strings() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Foo {
+        #[serde(deserialize_with = "toml::de::integer_from_str_or_int")]
+        a: i64,
+        #[serde(deserialize_with = "toml::de::integer_from_str_or_int")]
+        b: i64,
+        #[serde(deserialize_with = "toml::de::float_from_str_or_float")]
+        c: f64,
+        #[serde(deserialize_with = "toml::de::float_from_str_or_float")]
+        d: f64,
+    }
+
+    let foo: Foo = t!(toml::from_str("a = 1\nb = \"-2\"\nc = 1.5\nd = \"2.5\""));
+    assert_eq!(
+        foo,
+        Foo {
+            a: 1,
+            b: -2,
+            c: 1.5,
+            d: 2.5
+        }
+    );
+
+    let foo: Foo = t!(toml::from_str("a = 1\nb = 2\nc = 5\nd = \"-3\""));
+    assert_eq!(
+        foo,
+        Foo {
+            a: 1,
+            b: 2,
+            c: 5.0,
+            d: -3.0
+        }
+    );
+
+    match toml::from_str::<Foo>("a = \"one\"\nb = 2\nc = 1.0\nd = 2.0") {
+        Ok(_) => panic!("successful"),
+        Err(e) => assert_eq!(
+            e.to_string(),
+            "invalid value: string \"one\", expected an integer or a string containing an integer for key `a` at line 1 column 5"
+        ),
+    }
+}
+
+#[test]
+fn flatten_map() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Foo {
+        name: String,
+        #[serde(flatten)]
+        rest: BTreeMap<String, Value>,
+    }
+
+    let mut rest = BTreeMap::new();
+    rest.insert("a".to_string(), Value::Integer(1));
+    rest.insert("b".to_string(), Table(map! { c: 2 }));
+    rest.insert("d".to_string(), Value::String("x".to_string()));
+    let foo = Foo {
+        name: "foo".to_string(),
+        rest,
+    };
+    let s = t!(toml::to_string(&foo));
+    println!("{}", s);
+    assert_eq!(s, "name = \"foo\"\na = 1\nd = \"x\"\n\n[b]\nc = 2\n");
+    assert_eq!(foo, t!(toml::from_str(&s)));
+}
+
+#[test]
+fn flatten_with_settings() {
+    #[derive(Serialize)]
+    struct Inner {
+        z: i32,
+        a: i32,
+        when: toml::value::Datetime,
+    }
+
+    #[derive(Serialize)]
+    struct Rest {
+        list: Vec<Option<i32>>,
+        inner: Inner,
+    }
+
+    #[derive(Serialize)]
+    struct Foo {
+        name: String,
+        #[serde(flatten)]
+        rest: Rest,
+    }
+
+    let foo = Foo {
+        name: "foo".to_string(),
+        rest: Rest {
+            list: vec![Some(1), None, Some(2)],
+            inner: Inner {
+                z: 1,
+                a: 2,
+                when: "1979-05-27".parse().unwrap(),
+            },
+        },
+    };
+    assert_eq!(toml::to_string(&foo), Err(toml::ser::Error::NoneInArray));
+
+    let mut s = String::new();
+    {
+        let mut ser = toml::Serializer::new(&mut s);
+        ser.skip_none(true);
+        t!(foo.serialize(&mut ser));
+    }
+    assert_eq!(
+        s,
+        "name = \"foo\"\nlist = [1, 2]\n\n[inner]\nz = 1\na = 2\nwhen = 1979-05-27\n"
+    );
+}
+
+#[test]
+fn flatten_f32() {
+    #[derive(Serialize)]
+    struct Flat {
+        x: f32,
+        #[serde(flatten)]
+        rest: BTreeMap<String, Value>,
+    }
+
+    let flat = Flat {
+        x: 0.1,
+        rest: BTreeMap::new(),
+    };
+    assert_eq!(t!(toml::to_string(&flat)), "x = 0.1\n");
+}
+
+#[test]
+fn char_from_str() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Foo {
+        #[serde(deserialize_with = "toml::de::char_from_str")]
+        sep: char,
+    }
+
+    let foo: Foo = t!(toml::from_str("sep = 'é'"));
+    assert_eq!(foo, Foo { sep: 'é' });
+
+    for s in &["sep = 'ab'", "sep = ''"] {
+        match toml::from_str::<Foo>(s) {
+            Ok(_) => panic!("successful"),
+            Err(e) => assert!(
+                e.to_string()
+                    .contains("expected a string containing a single character"),
+                "{}",
+                e
+            ),
+        }
+    }
+}
+
+#[test]
+fn bool_from_str_or_bool() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Foo {
+        #[serde(deserialize_with = "toml::de::bool_from_str_or_bool")]
+        a: bool,
+        #[serde(deserialize_with = "toml::de::bool_from_str_or_bool")]
+        b: bool,
+        #[serde(deserialize_with = "toml::de::bool_from_str_or_bool")]
+        c: bool,
+    }
+
+    let foo: Foo = t!(toml::from_str("a = true\nb = \"FALSE\"\nc = 'True'"));
+    assert_eq!(
+        foo,
+        Foo {
+            a: true,
+            b: false,
+            c: true
+        }
+    );
+
+    match toml::from_str::<Foo>("a = 'yes'\nb = true\nc = true") {
+        Ok(_) => panic!("successful"),
+        Err(e) => assert_eq!(
+            e.to_string(),
+            "invalid value: string \"yes\", expected a boolean or a string containing `true` or `false` for key `a` at line 1 column 5"
+        ),
+    }
+}
+
+#[test]
+fn ordered_entries() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Foo {
+        #[serde(deserialize_with = "toml::de::ordered_entries")]
+        a: Vec<(String, i64)>,
+        #[serde(deserialize_with = "toml::de::ordered_entries")]
+        b: Vec<(String, Value)>,
+    }
+
+    let foo: Foo = t!(toml::from_str(
+        "a = { z = 1, y = 2, x = 3 }\n[b.d]\nv = 1\n[b.c]\nw = 2"
+    ));
+    assert_eq!(
+        foo,
+        Foo {
+            a: vec![
+                ("z".to_string(), 1),
+                ("y".to_string(), 2),
+                ("x".to_string(), 3)
+            ],
+            b: vec![
+                ("d".to_string(), Table(map! { v: 1 })),
+                ("c".to_string(), Table(map! { w: 2 })),
+            ],
+        }
+    );
+
+    match toml::from_str::<Foo>("a = [1]\n[b]") {
+        Ok(_) => panic!("successful"),
+        Err(e) => assert!(
+            e.to_string()
+                .starts_with("invalid type: sequence, expected a table for key `a`"),
+            "{}",
+            e
+        ),
+    }
+}
+
+#[test]
+fn from_str_reporting_unused() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Foo {
+        a: i64,
+        b: Option<Bar>,
+        c: Vec<Bar>,
+        d: Vec<Bar>,
+        e: BTreeMap<String, Bar>,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Bar {
+        x: i64,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Baz {
+        a: i64,
+        #[serde(flatten)]
+        rest: BTreeMap<String, i64>,
+    }
+
+    let doc = r#"
+        a = 1
+        unknown = 2
+        c = [{ x = 1 }, { x = 2, y = 3 }]
+        e = { k = { x = 1, "z.z" = [1] } }
+
+        [b]
+        x = 1
+        s = { t = 1 }
+
+        [[d]]
+        x = 1
+        [d.sub]
+        q = 1
+        [[d]]
+        x = 2
+        w = 1
+
+        [other]
+        a = 1
+        [other.nested]
+        b = 1
+    "#;
+    let (foo, unused) = t!(toml::from_str_reporting_unused::<Foo>(doc));
+    assert_eq!(foo.a, 1);
+    assert_eq!(foo.b, Some(Bar { x: 1 }));
+    assert_eq!(foo.d, vec![Bar { x: 1 }, Bar { x: 2 }]);
+    assert_eq!(
+        unused,
+        [
+            "unknown",
+            "c.1.y",
+            "e.k.\"z.z\"",
+            "b.s",
+            "d.0.sub",
+            "d.1.w",
+            "other",
+        ]
+    );
+
+    // Keys consumed by a flattened map are used.
+    let (baz, unused) = t!(toml::from_str_reporting_unused::<Baz>("a = 1\nn = 2"));
+    assert_eq!(baz.rest.get("n"), Some(&2));
+    assert!(unused.is_empty(), "{:?}", unused);
+
+    match toml::from_str_reporting_unused::<Foo>("a = 'x'") {
+        Ok(_) => panic!("successful"),
+        Err(e) => assert_eq!(
+            e.to_string(),
+            "invalid type: string \"x\", expected i64 for key `a` at line 1 column 5"
+        ),
+    }
+}
+
+#[test]
+fn array_of_tables_at_root() {
+    #[derive(Serialize)]
+    struct Foo {
+        a: i32,
+    }
+
+    assert_eq!(
+        toml::to_string(&vec![Foo { a: 1 }]),
+        Err(toml::ser::Error::ArrayOfTablesAtRoot)
+    );
+    assert_eq!(t!(toml::to_string(&vec![1, 2])), "[1, 2]");
+}
+
+#[test]
+fn none_in_array() {
+    #[derive(Serialize)]
+    struct Foo {
+        a: Vec<Option<i32>>,
+        b: Option<Vec<i32>>,
+    }
+
+    let foo = Foo {
+        a: vec![Some(1), None, Some(2)],
+        b: None,
+    };
+    assert_eq!(toml::to_string(&foo), Err(toml::ser::Error::NoneInArray));
+    assert_eq!(Value::try_from(&foo), Err(toml::ser::Error::NoneInArray));
+
+    let mut s = String::new();
+    {
+        let mut ser = toml::Serializer::new(&mut s);
+        ser.skip_none(true);
+        t!(foo.serialize(&mut ser));
+    }
+    assert_eq!(s, "a = [1, 2]\n");
+
+    let mut s = String::new();
+    {
+        let mut ser = toml::Serializer::new(&mut s);
+        ser.skip_none(true);
+        t!(vec![None::<i32>].serialize(&mut ser));
+    }
+    assert_eq!(s, "[]");
+}
+
+#[test]
+fn empty_table_as_inline() {
+    #[derive(Serialize)]
+    struct Empty {}
+
+    #[derive(Serialize)]
+    struct Foo {
+        name: &'static str,
+        tags: Vec<i32>,
+        features: BTreeMap<String, i32>,
+        empty: Empty,
+        nested: BTreeMap<String, BTreeMap<String, i32>>,
+        last: BTreeMap<String, i32>,
+    }
+
+    let foo = Foo {
+        name: "x",
+        tags: vec![],
+        features: BTreeMap::new(),
+        empty: Empty {},
+        nested: vec![("a".to_string(), BTreeMap::new())]
+            .into_iter()
+            .collect(),
+        last: BTreeMap::new(),
+    };
+
+    let to_string = |inline: bool| {
+        let mut s = String::new();
+        {
+            let mut ser = toml::Serializer::new(&mut s);
+            ser.empty_table_as_inline(inline);
+            t!(foo.serialize(&mut ser));
+        }
+        s
+    };
+
+    assert!(to_string(false).starts_with("name = \"x\"\ntags = []\n\n[features]\n\n[empty]\n"));
+    let s = to_string(true);
+    assert_eq!(
+        s,
+        "name = \"x\"\ntags = []\nfeatures = {}\nempty = {}\n\n[nested]\na = {}\n\n[last]\n"
+    );
+    assert_eq!(
+        t!(s.parse::<Value>()),
+        t!(to_string(false).parse::<Value>())
+    );
+}
+
+#[test]
+fn unit_struct_as_empty_table() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Marker;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Foo {
+        name: String,
+        enabled: Marker,
+    }
+
+    let foo = Foo {
+        name: "x".to_string(),
+        enabled: Marker,
+    };
+    assert_eq!(t!(toml::to_string(&foo)), "name = \"x\"\n\n[enabled]\n");
+    assert_eq!(
+        t!(toml::from_str::<Foo>("name = \"x\"\n\n[enabled]\n")),
+        foo
+    );
+
+    let mut s = String::new();
+    {
+        let mut ser = toml::Serializer::new(&mut s);
+        ser.empty_table_as_inline(true);
+        t!(foo.serialize(&mut ser));
+    }
+    assert_eq!(s, "name = \"x\"\nenabled = {}\n");
+    assert_eq!(t!(toml::from_str::<Foo>(&s)), foo);
+
+    let value = t!(Value::try_from(&foo));
+    assert_eq!(value["enabled"], Table(map! {}));
+    assert_eq!(t!(value.try_into::<Foo>()), foo);
+
+    match toml::from_str::<Foo>("name = \"x\"\nenabled = { a = 1 }\n") {
+        Ok(_) => panic!("successful"),
+        Err(e) => assert_eq!(
+            e.to_string(),
+            "invalid type: map, expected unit struct Marker for key `enabled` at line 2 column 11"
+        ),
+    }
+    match toml::from_str::<Foo>("name = \"x\"\n[enabled]\na = 1\n") {
+        Ok(_) => panic!("successful"),
+        Err(e) => assert_eq!(
+            e.to_string(),
+            "invalid type: map, expected unit struct Marker for key `enabled` at line 2 column 1"
+        ),
+    }
+
+    // A unit struct can't be a whole document.
+    assert_eq!(
+        toml::to_string(&Marker),
+        Err(toml::ser::Error::UnsupportedType)
+    );
+
+    // `()` still has no representation.
+    let mut unit = BTreeMap::new();
+    unit.insert("a", ());
+    assert_eq!(
+        toml::to_string(&unit),
+        Err(toml::ser::Error::UnsupportedType)
+    );
+}
+
+#[test]
+fn custom_error_path() {
+    use serde::ser::Error;
+
+    fn fail<S: serde::Serializer>(_: &i32, _: S) -> Result<S::Ok, S::Error> {
+        Err(S::Error::custom("bad value"))
+    }
+
+    #[derive(Serialize)]
+    struct Inner {
+        #[serde(serialize_with = "fail")]
+        x: i32,
+    }
+
+    #[derive(Serialize)]
+    struct Outer {
+        a: i32,
+        inner: Inner,
+    }
+
+    let err = toml::to_string(&Outer {
+        a: 1,
+        inner: Inner { x: 2 },
+    })
+    .unwrap_err();
+    assert_eq!(
+        err,
+        toml::ser::Error::Custom("bad value (at `inner.x`)".to_string())
+    );
+    assert_eq!(err.to_string(), "bad value (at `inner.x`)");
+
+    let err = toml::to_string(
+        &vec![("list", vec![Inner { x: 1 }])]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "bad value (at `list.x`)");
+
+    struct Root;
+    impl Serialize for Root {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            Err(S::Error::custom("bad root"))
+        }
+    }
+    let err = toml::to_string(&Root).unwrap_err();
+    assert_eq!(err, toml::ser::Error::Custom("bad root".to_string()));
+    assert_eq!(err.key(), None);
+
+    let err = toml::to_string(
+        &vec![("list", vec![Root])]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "bad root (at `list`)");
+}
+
+#[test]
+fn stringify_int_keys() {
+    use std::collections::HashMap;
+
+    let mut map = HashMap::new();
+    map.insert(1u8, "one".to_string());
+    map.insert(20u8, "twenty".to_string());
+    assert_eq!(toml::to_string(&map), Err(toml::ser::Error::KeyNotString));
+
+    let mut s = String::new();
+    {
+        let mut ser = toml::Serializer::new(&mut s);
+        ser.stringify_int_keys(true);
+        t!(map.serialize(&mut ser));
+    }
+    let mut lines: Vec<_> = s.lines().collect();
+    lines.sort();
+    assert_eq!(lines, ["1 = \"one\"", "20 = \"twenty\""]);
+    assert_eq!(t!(toml::from_str::<HashMap<u8, String>>(&s)), map);
+
+    let mut nested = BTreeMap::new();
+    nested.insert(
+        "servers",
+        vec![(-1i64, 2i64)].into_iter().collect::<BTreeMap<_, _>>(),
+    );
+    let opts = toml::ser::SerializeOptions::default().stringify_int_keys(true);
+    assert_eq!(
+        t!(toml::to_string_with(&nested, &opts)),
+        "[servers]\n-1 = 2\n"
+    );
+
+    // Maps nested in tables which are collected before being emitted
+    let opts = opts.sort_keys_by(|a, b| a.cmp(b));
+    assert_eq!(
+        t!(toml::to_string_with(&nested, &opts)),
+        "[servers]\n-1 = 2\n"
+    );
+}
+
+#[test]
+fn array_mixed_tables_and_values() {
+    let value = Table(map! { x: Array(vec![Integer(1), Table(map! { a: 1 })]) });
+    let err = toml::to_string(&value).unwrap_err();
+    assert_eq!(
+        err,
+        toml::ser::Error::ArrayMixedType {
+            key: "x".to_string(),
+            expected: "value",
+            found: "table"
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "arrays cannot mix tables and other values, expected a value but found a table (at `x`)"
+    );
+    assert_eq!(err.key(), Some("x"));
+
+    let value = Table(map! { x: Array(vec![Table(map! { a: 1 }), Integer(1)]) });
+    assert_eq!(
+        toml::to_string(&value),
+        Err(toml::ser::Error::ArrayMixedType {
+            key: "x".to_string(),
+            expected: "table",
+            found: "value"
+        })
+    );
+
+    // Mixing values of different types is fine
+    let value = Table(map! { x: Array(vec![Integer(1), Value::String("a".to_string())]) });
+    assert_eq!(t!(toml::to_string(&value)), "x = [1, \"a\"]\n");
+}
+
+#[test]
+fn integer128() {
+    #[derive(Serialize)]
+    struct Foo {
+        a: u128,
+        b: i128,
+    }
+
+    let foo = Foo {
+        a: u128::from(u64::MAX >> 1),
+        b: i128::from(i64::MIN),
+    };
+    assert_eq!(
+        t!(toml::to_string(&foo)),
+        "a = 9223372036854775807\nb = -9223372036854775808\n"
+    );
+    assert_eq!(
+        t!(Value::try_from(&foo)),
+        Table(map! { a: i64::MAX, b: i64::MIN })
+    );
+
+    let foo = Foo {
+        a: u128::from(u64::MAX),
+        b: 0,
+    };
+    let err = toml::to_string(&foo).unwrap_err();
+    assert_eq!(err.to_string(), "u128 value was out of range (at `a`)");
+    assert!(Value::try_from(&foo).is_err());
+
+    let foo = Foo { a: 0, b: i128::MIN };
+    let err = toml::to_string(&foo).unwrap_err();
+    assert_eq!(err.to_string(), "i128 value was out of range (at `b`)");
+}
+
+#[test]
+fn value_after_table_path() {
+    #[derive(Serialize)]
+    struct Inner {
+        table: BTreeMap<String, i32>,
+        value: i32,
+    }
+
+    #[derive(Serialize)]
+    struct Outer {
+        #[serde(rename = "a.b")]
+        inner: Inner,
+    }
+
+    let mut table = BTreeMap::new();
+    table.insert("x".to_string(), 1);
+    let outer = Outer {
+        inner: Inner { table, value: 1 },
+    };
+    let err = toml::to_string(&outer).unwrap_err();
+    assert_eq!(
+        err,
+        toml::ser::Error::ValueAfterTable {
+            key: "\"a.b\".value".to_string()
+        }
+    );
+    assert_eq!(err.key(), Some("\"a.b\".value"));
+    assert_eq!(
+        err.to_string(),
+        "values must be emitted before tables (at `\"a.b\".value`)"
+    );
+}
+
+#[test]
+fn ser_error_into_io_error() {
+    fn write() -> std::io::Result<String> {
+        Ok(toml::to_string(&vec![Value::Table(Map::new())])?)
+    }
+
+    let err = write().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.to_string(),
+        toml::ser::Error::ArrayOfTablesAtRoot.to_string()
+    );
+}
+
+#[test]
+fn from_reader() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Foo {
+        a: i64,
+        b: Vec<String>,
+    }
+
+    let foo: Foo = t!(toml::from_reader(&b"a = 1\nb = ['x']"[..]));
+    assert_eq!(
+        foo,
+        Foo {
+            a: 1,
+            b: vec!["x".to_string()]
+        }
+    );
+
+    let err = toml::from_reader::<_, Foo>(&b"a = 1\nb = ['\xff']"[..]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "input is not valid UTF-8: invalid utf-8 sequence of 1 bytes from index 12"
+    );
+}
+
+#[test]
+fn stream_array_of_tables() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Record {
+        id: i64,
+        tags: Option<Vec<String>>,
+        meta: Option<BTreeMap<String, i64>>,
+        points: Option<Vec<Point>>,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Point {
+        x: i64,
+    }
+
+    let doc = r#"
+        title = "log"
+
+        [[records]]
+        id = 1
+        tags = ["a"]
+
+        [other]
+        x = 1
+
+        [[records]]
+        id = 2
+        [records.meta]
+        size = 3
+        [[records.points]]
+        x = 1
+        [[records.points]]
+        x = 2
+
+        [[other.records]]
+        id = 4
+
+        [[records]]
+        id = "three"
+
+        [[records]]
+        id = 4
+    "#;
+    let mut records = toml::de::stream_array_of_tables::<Record>(doc, "records");
+    assert_eq!(
+        records.next().unwrap().unwrap(),
+        Record {
+            id: 1,
+            tags: Some(vec!["a".to_string()]),
+            meta: None,
+            points: None,
+        }
+    );
+    assert_eq!(
+        records.next().unwrap().unwrap(),
+        Record {
+            id: 2,
+            tags: None,
+            meta: Some(vec![("size".to_string(), 3)].into_iter().collect()),
+            points: Some(vec![Point { x: 1 }, Point { x: 2 }]),
+        }
+    );
+    assert_eq!(
+        records.next().unwrap().unwrap_err().to_string(),
+        "invalid type: string \"three\", expected i64 for key `records.id` at line 24 column 14"
+    );
+    assert_eq!(records.next().unwrap().unwrap().id, 4);
+    assert!(records.next().is_none());
+
+    let doc = "[[records]]\nid = 1\n[[records]]\nid = \n[[records]]\nid = 3\n";
+    let mut records = toml::de::stream_array_of_tables::<Record>(doc, "records");
+    assert_eq!(records.next().unwrap().unwrap().id, 1);
+    assert_eq!(
+        records.next().unwrap().unwrap_err().to_string(),
+        "expected a value, found a newline at line 4 column 6"
+    );
+    assert!(records.next().is_none());
+}
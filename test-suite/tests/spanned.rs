@@ -50,6 +50,7 @@ fn test_spanned_field() {
             assert_eq!(s.len(), foo.foo.end());
         }
         assert_eq!(expected, &s[foo.foo.start()..foo.foo.end()]);
+        assert_eq!(expected, &s[foo.foo.range()]);
 
         // Test for Spanned<> at the top level
         let foo_outer: Spanned<BareFoo<T>> = toml::from_str(s).unwrap();
@@ -247,3 +248,163 @@ fn test_spanned_array() {
     ",
     );
 }
+
+#[test]
+fn test_from_str_spanned() {
+    use toml::spanned_value::{SpannedValue, ValueKind};
+
+    fn table(value: &SpannedValue) -> &toml::spanned_value::SpannedTable {
+        match value.get_ref() {
+            ValueKind::Table(table) => table,
+            other => panic!("expected a table, found {}", other.type_str()),
+        }
+    }
+
+    let s = "a = 1\nb = [true, 'x']\nc = 1997-09-09\nd = { e = 2.5 }\n[f]\ng = \"h\"\n";
+    let doc = toml::from_str_spanned(s).unwrap();
+    assert_eq!(doc.span(), (0, s.len()));
+
+    let root = table(&doc);
+    assert_eq!(&s[root["a"].start()..root["a"].end()], "1");
+    assert_eq!(&s[root["b"].start()..root["b"].end()], "[true, 'x']");
+    assert_eq!(&s[root["c"].start()..root["c"].end()], "1997-09-09");
+    assert_eq!(&s[root["d"].start()..root["d"].end()], "{ e = 2.5 }");
+
+    match root["b"].get_ref() {
+        ValueKind::Array(array) => {
+            assert_eq!(array[1].get_ref(), &ValueKind::String("x".to_string()));
+            assert_eq!(&s[array[1].start()..array[1].end()], "'x'");
+        }
+        other => panic!("expected an array, found {}", other.type_str()),
+    }
+
+    let e = &table(&root["d"])["e"];
+    assert_eq!(e.get_ref(), &ValueKind::Float(2.5));
+    assert_eq!(&s[e.start()..e.end()], "2.5");
+
+    let g = &table(&root["f"])["g"];
+    assert_eq!(&s[g.start()..g.end()], "\"h\"");
+}
+
+#[test]
+fn test_value_span() {
+    let s = "a = [\n  1, # one\n  { b = [2] },\n]\nc =1979-05-27 07:32:00Z\nd = -1.5e3\ne = '''\nx'''\n";
+    let span = |key: &str| {
+        let offset = s.find(&format!("{} =", key)).unwrap() + key.len() + 2;
+        let (start, end) = toml::de::value_span(s, offset).unwrap();
+        &s[start..end]
+    };
+    assert_eq!(span("a"), "[\n  1, # one\n  { b = [2] },\n]");
+    assert_eq!(span("c"), "1979-05-27 07:32:00Z");
+    assert_eq!(span("d"), "-1.5e3");
+    assert_eq!(span("e"), "'''\nx'''");
+
+    assert!(toml::de::value_span("a = [1, 2", 3).is_err());
+    assert!(toml::de::value_span("a = ", 3).is_err());
+}
+
+#[test]
+fn test_raw_value() {
+    let s = "a = \"\\u00e9\" \nb = 1_000\nc = { d = 'x' }\ne = 1979-05-27 07:32:00Z\n";
+    let raw = |key: &str| {
+        let offset = s.find(&format!("{} =", key)).unwrap() + key.len() + 2;
+        toml::de::raw_value(s, offset).unwrap()
+    };
+    assert_eq!(raw("a"), "\"\\u00e9\"");
+    assert_eq!(raw("b"), "1_000");
+    assert_eq!(raw("c"), "{ d = 'x' }");
+    assert_eq!(raw("e"), "1979-05-27 07:32:00Z");
+
+    assert!(toml::de::raw_value("a = 'x", 3).is_err());
+}
+
+#[test]
+fn test_table_spans() {
+    let s = "a = 1\n[b]\r\nc = 2\n  [ b . \"c.d\" ]  # comment\n[[e]]\n[[e]]";
+    let spans = toml::de::table_spans(s).unwrap();
+    let spans = spans
+        .iter()
+        .map(|(key, (start, end))| (key.join("/"), &s[*start..*end]))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        [
+            ("b".to_string(), "[b]"),
+            ("b/c.d".to_string(), "[ b . \"c.d\" ]  # comment"),
+            ("e".to_string(), "[[e]]"),
+            ("e".to_string(), "[[e]]"),
+        ]
+    );
+
+    assert!(toml::de::table_spans("[a").is_err());
+}
+
+#[test]
+fn test_key_comments() {
+    let s = "\u{feff}# Title of the document\r\ntitle = 'x' # trailing\r\n\
+             # Not attached\n\n\
+             ##   The server\n[ server ]\n  # The port\n  port = 80\n\
+             #no space\n\"a.b\".c = { d = 1 }\n\
+             # Element\n[[server.hosts]]\nname = 'a'\n[[server.hosts]]\n";
+    let comments = toml::de::key_comments(s).unwrap();
+    let comments = comments
+        .iter()
+        .map(|(key, lines)| (key.as_str(), lines.iter().map(|l| l.as_str()).collect()))
+        .collect::<Vec<(_, Vec<_>)>>();
+    assert_eq!(
+        comments,
+        [
+            ("title", vec!["Title of the document"]),
+            ("server", vec!["#   The server"]),
+            ("server.port", vec!["The port"]),
+            ("server.\"a.b\".c", vec!["no space"]),
+            ("server.hosts", vec!["Element"]),
+            ("server.hosts.name", vec![]),
+            ("server.hosts", vec![]),
+        ]
+    );
+
+    assert!(toml::de::key_comments("a = ").is_err());
+}
+
+#[test]
+fn test_from_str_with_spans() {
+    #[derive(Deserialize)]
+    struct Foo {
+        a: i64,
+        b: Bar,
+        c: Vec<HashMap<String, Vec<i64>>>,
+    }
+    #[derive(Deserialize)]
+    struct Bar {
+        d: String,
+        e: Option<Spanned<i64>>,
+    }
+
+    let s = "a = 1\nignored = { x = 1 }\nc = [{ f = [2, 3] }]\n[b]\nd = 'x'\ne = 4\n";
+    let (foo, spans) = toml::from_str_with_spans::<Foo>(s).unwrap();
+    assert_eq!(foo.a, 1);
+    assert_eq!(foo.b.d, "x");
+    let e = foo.b.e.unwrap();
+    assert_eq!(e.start()..e.end(), spans["b.e"]);
+    assert_eq!(foo.c[0]["f"], [2, 3]);
+    let mut spans = spans
+        .into_iter()
+        .map(|(key, span)| (key, &s[span]))
+        .collect::<Vec<_>>();
+    spans.sort();
+    assert_eq!(
+        spans,
+        [
+            ("a".to_string(), "1"),
+            ("b.d".to_string(), "'x'"),
+            ("b.e".to_string(), "4"),
+            ("c".to_string(), "[{ f = [2, 3] }]"),
+            ("c.0".to_string(), "{ f = [2, 3] }"),
+            ("c.0.f".to_string(), "[2, 3]"),
+            ("c.0.f.0".to_string(), "2"),
+            ("c.0.f.1".to_string(), "3"),
+            ("ignored".to_string(), "{ x = 1 }"),
+        ]
+    );
+}
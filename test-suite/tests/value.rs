@@ -0,0 +1,729 @@
+extern crate toml;
+
+use toml::map::Map;
+use toml::Value;
+
+#[test]
+fn entry() {
+    let mut root = Value::Table(Map::new());
+    *root.entry("a").entry("b").entry("c") = Value::Integer(1);
+    root.entry("a").entry("d");
+    assert_eq!(root.to_string(), "[a.b]\nc = 1\n\n[a.d]\n");
+
+    // Existing values are returned as-is.
+    assert_eq!(*root.entry("a").entry("b").entry("c"), Value::Integer(1));
+}
+
+#[test]
+#[should_panic(expected = "cannot call entry on a integer")]
+fn entry_not_table() {
+    Value::Integer(1).entry("a");
+}
+
+#[test]
+fn get_or_insert_with() {
+    let mut root: Value = "[a]\nb = 1".parse().unwrap();
+    *root.get_or_insert_with("a.c.d", || Value::Integer(2)) = Value::Integer(3);
+    root.get_or_insert_with("e", || Value::Boolean(true));
+    assert_eq!(root.to_string(), "e = true\n\n[a]\nb = 1\n\n[a.c]\nd = 3\n");
+
+    // Existing values are returned as-is.
+    let b = root.get_or_insert_with("a.\"b\"", || unreachable!());
+    assert_eq!(*b, Value::Integer(1));
+}
+
+#[test]
+#[should_panic(expected = "cannot insert at `a.b.c` into a integer")]
+fn get_or_insert_with_not_table() {
+    let mut root: Value = "a.b = 1".parse().unwrap();
+    root.get_or_insert_with("a.b.c", || Value::Integer(2));
+}
+
+#[test]
+fn index() {
+    let mut value: Value = "a = [1, { b = 'x' }]".parse().unwrap();
+    assert_eq!(value["a"][1]["b"].as_str(), Some("x"));
+    value["a"][0] = Value::Integer(2);
+    assert_eq!(value["a"][0].as_integer(), Some(2));
+}
+
+#[test]
+#[should_panic(expected = "index not found")]
+fn index_missing_key() {
+    let value: Value = "a = 1".parse().unwrap();
+    let _ = &value["b"];
+}
+
+#[test]
+#[should_panic(expected = "index not found")]
+fn index_out_of_bounds() {
+    let value: Value = "a = [1]".parse().unwrap();
+    let _ = &value["a"][1];
+}
+
+#[test]
+fn array_element_type() {
+    let value: Value = "a = ['x', 'y']\nb = [[1], [2]]\nc = []\nd = 1"
+        .parse()
+        .unwrap();
+    assert_eq!(value["a"].array_element_type(), Some("string"));
+    assert_eq!(value["b"].array_element_type(), Some("array"));
+    assert_eq!(value["c"].array_element_type(), None);
+    assert_eq!(value["d"].array_element_type(), None);
+}
+
+#[test]
+fn as_table_array() {
+    let value: Value = "a = [{ b = 1 }, {}]\nc = [{ b = 1 }, 2]\nd = []\n[[e]]\nf = 1"
+        .parse()
+        .unwrap();
+    let tables = value["a"].as_table_array().unwrap();
+    assert_eq!(tables.len(), 2);
+    assert_eq!(tables[0]["b"], Value::Integer(1));
+    assert!(tables[1].is_empty());
+    assert_eq!(value["c"].as_table_array(), None);
+    assert_eq!(value["d"].as_table_array(), Some(vec![]));
+    assert_eq!(
+        value["e"].as_table_array().unwrap()[0]["f"],
+        Value::Integer(1)
+    );
+    assert_eq!(value["e"][0]["f"].as_table_array(), None);
+}
+
+#[test]
+fn array_push_and_extend() {
+    let mut servers = Value::Array(Vec::new());
+    for name in &["a", "b"] {
+        let mut server = Map::new();
+        server.insert("name".to_string(), Value::from(*name));
+        servers.array_push(Value::Table(server)).unwrap();
+    }
+    servers
+        .array_extend(vec![Value::Table(Map::new())])
+        .unwrap();
+    let mut root = Map::new();
+    root.insert("servers".to_string(), servers);
+    assert_eq!(
+        Value::Table(root).to_string(),
+        "[[servers]]\nname = \"a\"\n\n[[servers]]\nname = \"b\"\n\n[[servers]]\n"
+    );
+
+    let mut value = Value::Integer(1);
+    assert_eq!(
+        value.array_push(Value::Boolean(true)),
+        Err(Value::Boolean(true))
+    );
+    let rejected = value.array_extend(vec![Value::Integer(2)]).unwrap_err();
+    assert_eq!(rejected, [Value::Integer(2)]);
+    assert_eq!(value, Value::Integer(1));
+}
+
+#[test]
+fn into_table_and_array() {
+    let value: Value = "a = [1, 2]\n[b]\nc = 3".parse().unwrap();
+    let mut table = value.into_table().unwrap();
+    let array = table.remove("a").unwrap().into_array().unwrap();
+    assert_eq!(array, [Value::Integer(1), Value::Integer(2)]);
+
+    let b = table.remove("b").unwrap();
+    let b = b.into_array().unwrap_err();
+    assert_eq!(b.into_table().unwrap()["c"], Value::Integer(3));
+    assert_eq!(Value::Integer(1).into_table(), Err(Value::Integer(1)));
+}
+
+#[test]
+fn into_string_map() {
+    let value: Value = "a = 'x y'\nb = 1\nc = 1.0\nd = true\ne = 1979-05-27 07:32:00z"
+        .parse()
+        .unwrap();
+    let map = value.into_string_map().unwrap();
+    let entries: Vec<_> = map.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    assert_eq!(
+        entries,
+        [
+            ("a", "x y"),
+            ("b", "1"),
+            ("c", "1.0"),
+            ("d", "true"),
+            ("e", "1979-05-27T07:32:00Z"),
+        ]
+    );
+
+    let value: Value = "a = 1\nb = [1]".parse().unwrap();
+    assert_eq!(value.into_string_map(), None);
+    let value: Value = "a = 1\n[b]".parse().unwrap();
+    assert_eq!(value.into_string_map(), None);
+    assert_eq!(Value::Integer(1).into_string_map(), None);
+}
+
+#[test]
+fn from_iter() {
+    let table: Value = vec![
+        ("a".to_string(), Value::Integer(1)),
+        ("b".to_string(), Value::Boolean(true)),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(table, "a = 1\nb = true".parse::<Value>().unwrap());
+
+    let array: Value = (1..4).map(Value::Integer).collect();
+    assert_eq!(
+        array,
+        Value::Array(vec![
+            Value::Integer(1),
+            Value::Integer(2),
+            Value::Integer(3)
+        ])
+    );
+}
+
+#[test]
+fn loose_eq() {
+    let a: Value = "a = 1\nb = [2, 3.5]\n[c]\nd = 4.0".parse().unwrap();
+    let b: Value = "a = 1.0\nb = [2.0, 3.5]\n[c]\nd = 4".parse().unwrap();
+    assert!(a != b);
+    assert!(a.loose_eq(&b));
+    assert!(b.loose_eq(&a));
+
+    let c: Value = "a = 1\nb = [2, 3.5]\n[c]\nd = 4.5".parse().unwrap();
+    assert!(!a.loose_eq(&c));
+    assert!(!Value::Integer(1).loose_eq(&Value::String("1".to_string())));
+}
+
+#[test]
+fn to_string_pretty() {
+    use toml::value::PrettyOptions;
+
+    let value: Value = "a = [1, 2, 3]\nb = [4]\n[c]\nd = 'x'\n[e]\nf = 1"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        value.to_string_pretty(PrettyOptions::default()),
+        "a = [\n    1,\n    2,\n    3,\n]\nb = [4]\n\n[c]\nd = 'x'\n\n[e]\nf = 1\n"
+    );
+
+    let opts = PrettyOptions {
+        blank_line_between_tables: false,
+        array_wrap_threshold: Some(2),
+        indent: 2,
+    };
+    let s = value.to_string_pretty(opts);
+    assert_eq!(
+        s,
+        "a = [\n  1,\n  2,\n  3,\n]\nb = [4]\n[c]\nd = 'x'\n[e]\nf = 1\n"
+    );
+    assert_eq!(s.parse::<Value>().unwrap(), value);
+
+    let opts = PrettyOptions {
+        array_wrap_threshold: None,
+        ..PrettyOptions::default()
+    };
+    assert_eq!(
+        value.to_string_pretty(opts),
+        "a = [1, 2, 3]\nb = [4]\n\n[c]\nd = 'x'\n\n[e]\nf = 1\n"
+    );
+}
+
+#[test]
+fn walk_mut() {
+    let mut value: Value =
+        "password = 'a'\n[[servers]]\nname = 'x'\npassword = 'b'\n[db]\nport = 1"
+            .parse()
+            .unwrap();
+    let mut paths = Vec::new();
+    value.walk_mut(|path, value| {
+        paths.push(path.join("."));
+        if path.last() == Some(&"password") && value.is_str() {
+            *value = Value::String("***".to_string());
+        }
+        if let Value::Integer(ref mut i) = *value {
+            *i += 1;
+        }
+    });
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "",
+            "db",
+            "db.port",
+            "password",
+            "servers",
+            "servers",
+            "servers.name",
+            "servers.password",
+        ]
+    );
+    assert_eq!(
+        value,
+        "password = '***'\n[[servers]]\nname = 'x'\npassword = '***'\n[db]\nport = 2"
+            .parse::<Value>()
+            .unwrap()
+    );
+}
+
+#[test]
+fn diff() {
+    use toml::value::Change;
+
+    let old: Value = "a = 1\nb = 'x'\nc = [1, 2, 3]\n\"d.e\" = 1\n[f]\ng = true"
+        .parse()
+        .unwrap();
+    let new: Value = "a = 1\nb = 2\nc = [1, 5]\nh = 'new'\n[f]\ng = false"
+        .parse()
+        .unwrap();
+
+    let mut changes = old.diff(&new);
+    changes.sort_by_key(|c| match *c {
+        Change::Added(ref p, ..) | Change::Removed(ref p, ..) | Change::Modified(ref p, ..) => {
+            p.clone()
+        }
+    });
+    assert_eq!(
+        changes,
+        [
+            Change::Removed("\"d.e\"".to_string(), Value::Integer(1)),
+            Change::Modified(
+                "b".to_string(),
+                Value::String("x".to_string()),
+                Value::Integer(2)
+            ),
+            Change::Modified("c.1".to_string(), Value::Integer(2), Value::Integer(5)),
+            Change::Removed("c.2".to_string(), Value::Integer(3)),
+            Change::Modified(
+                "f.g".to_string(),
+                Value::Boolean(true),
+                Value::Boolean(false)
+            ),
+            Change::Added("h".to_string(), Value::String("new".to_string())),
+        ]
+    );
+
+    assert!(old.diff(&old).is_empty());
+    assert_eq!(
+        Value::Integer(1).diff(&Value::Float(1.0)),
+        [Change::Modified(
+            String::new(),
+            Value::Integer(1),
+            Value::Float(1.0)
+        )]
+    );
+}
+
+#[test]
+fn parse_path() {
+    let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(toml::parse_path("a"), Some(strings(&["a"])));
+    assert_eq!(
+        toml::parse_path("table.\"name.other\".value"),
+        Some(strings(&["table", "name.other", "value"]))
+    );
+    assert_eq!(
+        toml::parse_path("a . 'b.c' . 0"),
+        Some(strings(&["a", "b.c", "0"]))
+    );
+    assert_eq!(toml::parse_path("\"\""), Some(strings(&[""])));
+    assert_eq!(toml::parse_path(""), None);
+    assert_eq!(toml::parse_path("a."), None);
+    assert_eq!(toml::parse_path(".a"), None);
+    assert_eq!(toml::parse_path("a..b"), None);
+    assert_eq!(toml::parse_path("a b"), None);
+    assert_eq!(toml::parse_path("'''a'''"), None);
+}
+
+#[test]
+fn lookup() {
+    let mut value: Value = "a = [1, { b = 2 }]\n[\"c.d\"]\ne = 'x'".parse().unwrap();
+    assert_eq!(value.lookup("a.0"), Some(&Value::Integer(1)));
+    assert_eq!(value.lookup("a.1.b"), Some(&Value::Integer(2)));
+    assert_eq!(
+        value.lookup("\"c.d\".e"),
+        Some(&Value::String("x".to_string()))
+    );
+    assert_eq!(value.lookup("a.2"), None);
+    assert_eq!(value.lookup("a.x"), None);
+    assert_eq!(value.lookup("c.d.e"), None);
+    assert_eq!(value.lookup("a..0"), None);
+
+    *value.lookup_mut("a.1.b").unwrap() = Value::Integer(3);
+    assert_eq!(value["a"][1]["b"], Value::Integer(3));
+}
+
+#[test]
+fn lookup_empty_segments() {
+    let mut value: Value = "a = 1\n[b.\"\"]\nc = 3".parse().unwrap();
+    for path in &["", ".", "a.", ".a", "b..c", "b.", "..", " . "] {
+        assert_eq!(value.lookup(path), None, "{:?}", path);
+        assert_eq!(value.lookup_ci(path), None, "{:?}", path);
+        assert!(value.lookup_mut(path).is_none(), "{:?}", path);
+        assert!(!value.contains_path(path), "{:?}", path);
+    }
+    assert_eq!(value.lookup("b.\"\".c"), Some(&Value::Integer(3)));
+    assert_eq!(value.lookup("a"), Some(&Value::Integer(1)));
+}
+
+#[test]
+fn contains_key_and_path() {
+    let value: Value = "a = [1, { b = 2 }]\n\"c.d\" = 3".parse().unwrap();
+    assert!(value.contains_key("a"));
+    assert!(value.contains_key("c.d"));
+    assert!(!value.contains_key("c"));
+    assert!(!value["a"].contains_key("0"));
+
+    assert!(value.contains_path("a.1.b"));
+    assert!(value.contains_path("\"c.d\""));
+    assert!(!value.contains_path("c.d"));
+    assert!(!value.contains_path("a.2"));
+    assert!(!value.contains_path("a..b"));
+}
+
+#[test]
+fn lookup_ci() {
+    let value: Value = "[Server]\nPort = 1\nhosts = ['a']\n[server]\nport = 2"
+        .parse()
+        .unwrap();
+    assert_eq!(value.lookup_ci("SERVER.port"), Some(&Value::Integer(1)));
+    assert_eq!(
+        value.lookup_ci("server.HOSTS.0"),
+        Some(&value["Server"]["hosts"][0])
+    );
+    assert_eq!(value.lookup_ci("server.missing"), None);
+    assert_eq!(value.lookup_ci("server.port.x"), None);
+    assert_eq!(value.lookup("SERVER.port"), None);
+}
+
+#[test]
+fn leaf_count_and_max_depth() {
+    let flat: Value = "a = 1\nb = 'x'\nc = true".parse().unwrap();
+    assert_eq!(flat.leaf_count(), 3);
+    assert_eq!(flat.max_depth(), 1);
+
+    let dotted: Value = "a.b.c = 1".parse().unwrap();
+    assert_eq!(dotted.leaf_count(), 1);
+    assert_eq!(dotted.max_depth(), 3);
+
+    let nested: Value = "a = [[1, 2], []]\nb = {}\n[[c]]\nd = 3".parse().unwrap();
+    assert_eq!(nested.leaf_count(), 3);
+    assert_eq!(nested.max_depth(), 3);
+
+    assert_eq!(Value::Integer(1).leaf_count(), 1);
+    assert_eq!(Value::Integer(1).max_depth(), 0);
+}
+
+#[test]
+fn apply_defaults() {
+    let mut value: Value = "\
+        d = 'set'
+        e = [1]
+        [a]
+        b = 1
+        f = 'not a table'
+    "
+    .parse()
+    .unwrap();
+    let defaults: Value = "\
+        d = 'default'
+        e = [2, 3]
+        g = true
+        [a]
+        b = 2
+        c = 3
+        [a.f]
+        h = 4
+        [i.j]
+        k = 5
+    "
+    .parse()
+    .unwrap();
+    value.apply_defaults(&defaults);
+    assert_eq!(
+        value,
+        "\
+            d = 'set'
+            e = [1]
+            g = true
+            [a]
+            b = 1
+            c = 3
+            f = 'not a table'
+            [i.j]
+            k = 5
+        "
+        .parse::<Value>()
+        .unwrap()
+    );
+
+    let mut value = Value::Integer(1);
+    value.apply_defaults(&defaults);
+    assert_eq!(value, Value::Integer(1));
+}
+
+#[test]
+fn retain() {
+    let mut value: Value = "\
+        internal_a = 1
+        b = 2
+        c = [{ internal_d = 3, e = 4 }, 5]
+        [internal_f]
+        g = 6
+        [h]
+        internal_i = 7
+        j = 8
+        [h.k]
+        internal_l = 9
+    "
+    .parse()
+    .unwrap();
+    value.retain(|key, _| !key.starts_with("internal_"));
+    assert_eq!(
+        value,
+        "b = 2\nc = [{ e = 4 }, 5]\n[h]\nj = 8\n[h.k]"
+            .parse::<Value>()
+            .unwrap()
+    );
+
+    let mut value: Value = "a = 1\nb = 'x'\n[c]\nd = 2".parse().unwrap();
+    value.retain(|_, value| !value.is_integer());
+    assert_eq!(value, "b = 'x'\n[c]".parse::<Value>().unwrap());
+}
+
+#[test]
+fn as_f64_lossy() {
+    let value: Value = "a = 1\nb = 1.5\nc = '1'\nd = 9007199254740993"
+        .parse()
+        .unwrap();
+    assert_eq!(value["a"].as_f64_lossy(), Some(1.0));
+    assert_eq!(value["b"].as_f64_lossy(), Some(1.5));
+    assert_eq!(value["c"].as_f64_lossy(), None);
+    assert_eq!(value["d"].as_f64_lossy(), Some(9007199254740992.0));
+}
+
+#[test]
+fn validate() {
+    use toml::validate::{Schema, ValidationError};
+
+    let schema = Schema::table()
+        .required("name", Schema::of_type("string"))
+        .optional("tags", Schema::of_type("array"))
+        .required(
+            "server",
+            Schema::table()
+                .required("host", Schema::of_type("string"))
+                .required("port", Schema::of_type("integer"))
+                .optional("tls", Schema::any()),
+        )
+        .optional("db.main", Schema::table().required("url", Schema::any()));
+
+    let value: Value = "name = 'app'\n[server]\nhost = 'x'\nport = 1\ntls = true"
+        .parse()
+        .unwrap();
+    assert_eq!(value.validate(&schema), Ok(()));
+
+    let value: Value = "name = 1\ntags = 'a'\nextra = 1\n[server]\nport = '1'\n[\"db.main\"]"
+        .parse()
+        .unwrap();
+    let errors = value.validate(&schema).unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            ValidationError::WrongType {
+                path: "name".to_string(),
+                expected: "string",
+                found: "integer",
+            },
+            ValidationError::WrongType {
+                path: "tags".to_string(),
+                expected: "array",
+                found: "string",
+            },
+            ValidationError::MissingKey("server.host".to_string()),
+            ValidationError::WrongType {
+                path: "server.port".to_string(),
+                expected: "integer",
+                found: "string",
+            },
+            ValidationError::MissingKey("\"db.main\".url".to_string()),
+        ]
+    );
+    assert_eq!(errors[2].to_string(), "missing key `server.host`");
+
+    let errors = Value::Integer(1).validate(&schema).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].to_string(),
+        "expected type `table`, found `integer`"
+    );
+}
+
+#[test]
+fn flatten() {
+    let value: Value = "a.b = 1\nc = [2, [3]]\nd = []\n[e]\n[[f]]\ng = 'x'"
+        .parse()
+        .unwrap();
+    let flat = value.flatten(".");
+    let keys: Vec<_> = flat.keys().map(|k| k.as_str()).collect();
+    assert_eq!(keys, ["a.b", "c.0", "c.1.0", "d", "e", "f.0.g"]);
+    assert_eq!(flat["a.b"], Value::Integer(1));
+    assert_eq!(flat["c.1.0"], Value::Integer(3));
+    assert_eq!(flat["d"], Value::Array(vec![]));
+    assert_eq!(flat["e"], Value::Table(Map::new()));
+    assert_eq!(flat["f.0.g"], Value::String("x".to_string()));
+
+    let flat = value.flatten("__");
+    assert_eq!(flat["f__0__g"], Value::String("x".to_string()));
+
+    let flat = Value::Integer(1).flatten(".");
+    assert_eq!(flat[""], Value::Integer(1));
+}
+
+#[test]
+fn leaf_paths() {
+    let value: Value = "\
+        a = 1
+        b = []
+        c = [2, [3], { d = 4 }]
+        \"e.f\" = { g = 'x' }
+        [h]
+        [[servers]]
+        host = 'y'
+    "
+    .parse()
+    .unwrap();
+    let paths = value.leaf_paths();
+    assert_eq!(
+        paths,
+        ["a", "c.0", "c.1.0", "c.2.d", "\"e.f\".g", "servers.0.host"]
+    );
+    for path in &paths {
+        assert!(value.lookup(path).is_some(), "{}", path);
+    }
+    assert_eq!(Value::Integer(1).leaf_paths(), [""]);
+}
+
+#[test]
+fn unflatten() {
+    let value: Value = "a.b = 1\nc = 'x'\nd = []\n[\"e.f\"]\ng = true"
+        .parse()
+        .unwrap();
+    let pairs = vec![
+        ("a.b".to_string(), Value::Integer(1)),
+        ("c".to_string(), Value::String("x".to_string())),
+        ("d".to_string(), Value::Array(vec![])),
+        ("\"e.f\".g".to_string(), Value::Boolean(true)),
+    ];
+    assert_eq!(Value::unflatten(pairs, ".").unwrap(), value);
+    assert_eq!(Value::unflatten(value.flatten("."), ".").unwrap(), value);
+
+    let value: Value = "\"e.f\" = 1\n\"a b\".c = 2\n\"é\" = 3".parse().unwrap();
+    let flat = value.flatten(".");
+    let keys: Vec<_> = flat.keys().map(|k| k.as_str()).collect();
+    assert_eq!(keys, ["\"a b\".c", "\"e.f\"", "\"é\""]);
+    assert_eq!(Value::unflatten(flat, ".").unwrap(), value);
+
+    let flat = "a.b.c = 1\nd = 2".parse::<Value>().unwrap().flatten("__");
+    assert_eq!(
+        Value::unflatten(flat, "__").unwrap(),
+        "a.b.c = 1\nd = 2".parse::<Value>().unwrap()
+    );
+
+    let err = |pairs: &[(&str, i64)]| {
+        let pairs = pairs
+            .iter()
+            .map(|&(k, v)| (k.to_string(), Value::Integer(v)));
+        Value::unflatten(pairs, ".").unwrap_err().to_string()
+    };
+    assert_eq!(
+        err(&[("a", 1), ("a.b", 2)]),
+        "conflicting values for key `a.b`"
+    );
+    assert_eq!(
+        err(&[("a.b", 1), ("a", 2)]),
+        "conflicting values for key `a`"
+    );
+    assert_eq!(
+        err(&[("a.b", 1), ("a . b", 2)]),
+        "conflicting values for key `a . b`"
+    );
+    assert_eq!(err(&[("a..b", 1)]), "invalid key path: `a..b`");
+
+    for value in &[
+        Value::Integer(1),
+        Value::Array(vec![]),
+        Value::Table(Map::new()),
+    ] {
+        assert_eq!(&Value::unflatten(value.flatten("."), ".").unwrap(), value);
+        assert_eq!(&Value::unflatten(value.flatten("__"), "__").unwrap(), value);
+    }
+}
+
+#[test]
+fn rename_key() {
+    use toml::value::RenameError;
+
+    let mut value: Value = "a = 1\nb = 2\nc = 3\n[[t]]\nx = 'x'\n[u]\ny = 1\nz = 2"
+        .parse()
+        .unwrap();
+
+    value.rename_key("b", "bb").unwrap();
+    assert_eq!(
+        value.as_table().unwrap().keys().collect::<Vec<_>>(),
+        ["a", "bb", "c", "t", "u"]
+    );
+    assert_eq!(value["bb"].as_integer(), Some(2));
+    assert!(value.get("b").is_none());
+
+    value.rename_key("t.0.x", "w").unwrap();
+    assert_eq!(value["t"][0]["w"].as_str(), Some("x"));
+    value.rename_key("u.y", "y").unwrap();
+    assert_eq!(value["u"]["y"].as_integer(), Some(1));
+
+    assert_eq!(
+        value.rename_key("u.y", "z"),
+        Err(RenameError::KeyExists("u.z".to_string()))
+    );
+    assert_eq!(
+        value.rename_key("u.missing", "v"),
+        Err(RenameError::MissingKey("u.missing".to_string()))
+    );
+    assert_eq!(
+        value.rename_key("a.b", "v"),
+        Err(RenameError::MissingKey("a.b".to_string()))
+    );
+    assert_eq!(
+        value.rename_key("t.1.x", "v"),
+        Err(RenameError::MissingKey("t.1.x".to_string()))
+    );
+    value.rename_key("u.z", "a b").unwrap();
+    assert_eq!(
+        value.rename_key("u.y", "a b").unwrap_err().to_string(),
+        "key `u.\"a b\"` already exists"
+    );
+    assert_eq!(
+        value.rename_key("u..y", "v").unwrap_err().to_string(),
+        "no key at `u..y`"
+    );
+}
+
+#[test]
+fn coerce_numbers() {
+    let mut value: Value =
+        "a = [1, 2.5, 3]\nb = [1, 2]\nc = 1\nd = [[1, 0.5], [2]]\n[[e]]\nf = [2, 1e3]"
+            .parse()
+            .unwrap();
+    value.coerce_numbers();
+    let expected: Value =
+        "a = [1.0, 2.5, 3.0]\nb = [1, 2]\nc = 1\nd = [[1.0, 0.5], [2]]\n[[e]]\nf = [2.0, 1e3]"
+            .parse()
+            .unwrap();
+    assert_eq!(value, expected);
+
+    let mut value = Value::Array(vec![
+        Value::Integer(1),
+        Value::String("x".to_string()),
+        Value::Float(2.0),
+    ]);
+    value.coerce_numbers();
+    assert_eq!(value.to_string(), "[1.0, \"x\", 2.0]");
+}